@@ -0,0 +1,195 @@
+use crate::{encode_board, Board, Cell, DirectionEnum, WallEnum};
+use crate::cnf::{degree, exactly, loop_cuts, sat_solutions};
+use crate::design::{alternative_with_all_counts, design, DesignErrorEnum};
+use crate::generator::{Answer, Generated};
+use crate::random::Rng;
+use crate::sat::{Lit, Solver};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArtErrorEnum {
+    Adjacent(Vec<(usize, usize)>),  // 隣り合っている黒マス
+    NoLoop,                         // 黒マス以外にループを引けない
+    NotForced(Vec<(usize, usize)>), // ヒントを置いても黒マスかどうか決まらなかったマス
+    Design(DesignErrorEnum),        // 引いたループからヒントを選べなかった
+}
+
+const MAX_TRIES: usize = 40;
+
+// 型紙の黒マス以外に、ヒントのマスができるだけ少なくなるようにループを引く
+// clueで指定したマスはヒントにする
+fn route(mask: &[Vec<bool>], clue: &[bool]) -> Option<Vec<Vec<DirectionEnum>>> {
+    let (height, width) = (mask.len(), mask[0].len());
+    let free = |i: usize, j: usize| !mask[i][j];
+    let mut solver = Solver::new();
+    let on_loop: Vec<Lit> = (0..height * width).map(|k| if free(k / width, k % width) { solver.new_var() } else { 0 }).collect();
+    let mut edges: Vec<(usize, usize, DirectionEnum, Lit)> = Vec::new();
+    for i in 0..height {
+        for j in 0..width {
+            if i + 1 < height && free(i, j) && free(i + 1, j) {
+                edges.push((i * width + j, (i + 1) * width + j, DirectionEnum::Down, solver.new_var()));
+            }
+            if j + 1 < width && free(i, j) && free(i, j + 1) {
+                edges.push((i * width + j, i * width + j + 1, DirectionEnum::Right, solver.new_var()));
+            }
+        }
+    }
+    let cells: Vec<usize> = (0..height * width).filter(|k| on_loop[*k] != 0).collect();
+    if cells.is_empty() {
+        return None;
+    }
+
+    // ループ上のマスは線が2本、それ以外は0本
+    for k in cells.iter() {
+        let u = on_loop[*k];
+        let lines: Vec<Lit> = edges.iter().filter(|(a, b, _, _)| a == k || b == k).map(|(_, _, _, v)| *v).collect();
+        for clause in degree(&lines, u) {
+            solver.add_clause(&clause);
+        }
+        if clue[*k] {
+            solver.add_clause(&[-u]);
+        }
+    }
+    let loop_lits: Vec<Lit> = cells.iter().map(|k| on_loop[*k]).collect();
+    solver.add_clause(&loop_lits);
+
+    let cut_edges: Vec<(usize, usize, Lit)> = edges.iter().map(|(a, b, _, v)| (*a, *b, *v)).collect();
+
+    // ヒントのマスの数を、少ない方から順に試す
+    let off: Vec<Lit> = loop_lits.iter().map(|u| -u).collect();
+    let mut count = cells.iter().filter(|k| clue[**k]).count();
+    while count + 4 <= cells.len() {
+        let guard = solver.new_var();
+        let clauses = exactly(&off, count, &mut || solver.new_var());
+        for mut clause in clauses {
+            clause.push(-guard);
+            solver.add_clause(&clause);
+        }
+        loop {
+            if !solver.solve(&[guard]) {
+                break;
+            }
+            let cuts = loop_cuts(height * width, &cut_edges, |k| Some(on_loop[k]).filter(|u| *u != 0), |lit| solver.model_value(lit));
+            if cuts.is_empty() {
+                let mut lines = vec![Vec::new(); height * width];
+                for (a, b, dir, v) in edges.iter() {
+                    if solver.model_value(*v) {
+                        let (forward, backward) = if *dir == DirectionEnum::Down { (DirectionEnum::Down, DirectionEnum::Up) } else { (DirectionEnum::Right, DirectionEnum::Left) };
+                        lines[*a].push(forward);
+                        lines[*b].push(backward);
+                    }
+                }
+                return Some(lines);
+            }
+            // 分かれたループを外と繋ぐ
+            for cut in cuts {
+                solver.add_clause(&cut);
+            }
+        }
+        solver.add_clause(&[-guard]);
+        count += 1;
+    }
+    None
+}
+
+// 型紙の通りに黒マスを塗るとただ一つの解になる問題を作る
+// 黒マス以外はループが通るかヒントになる。決まらないときはループ上のマスをヒントにして引き直す
+pub fn art(mask: &[Vec<bool>], seed: u64) -> Result<Generated, ArtErrorEnum> {
+    let height = mask.len();
+    let width = if height == 0 { 0 } else { mask[0].len() };
+    if width == 0 || mask.iter().any(|row| row.len() != width) {
+        return Err(ArtErrorEnum::NoLoop);
+    }
+    let mut adjacent: Vec<(usize, usize)> = Vec::new();
+    for i in 0..height {
+        for j in 0..width {
+            let touching = (i + 1 < height && mask[i + 1][j]) || (j + 1 < width && mask[i][j + 1]) || (i > 0 && mask[i - 1][j]) || (j > 0 && mask[i][j - 1]);
+            if mask[i][j] && touching {
+                adjacent.push((i, j));
+            }
+        }
+    }
+    if !adjacent.is_empty() {
+        return Err(ArtErrorEnum::Adjacent(adjacent));
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut clue = vec![false; height * width];
+    let mut unforced: Vec<(usize, usize)> = Vec::new();
+    let allowed = vec![vec![true; width]; height];
+    for tries in 0..MAX_TRIES {
+        let lines = match route(mask, &clue) {
+            Some(lines) => lines,
+            None if tries == 0 => return Err(ArtErrorEnum::NoLoop),
+            None => break,
+        };
+        let answer = Answer {
+            height,
+            width,
+            shaded: mask.iter().flatten().cloned().collect(),
+            clues: (0..height * width).map(|k| if lines[k].is_empty() && !mask[k / width][k % width] { Some(DirectionEnum::None) } else { None }).collect(),
+            lines,
+        };
+        let drawn = Board((0..height).map(|i| (0..width).map(|j| {
+            let lines = &answer.lines[i * width + j];
+            if mask[i][j] {
+                Cell::Wall(WallEnum::Wall)
+            } else if lines.is_empty() {
+                Cell::Unknown
+            } else {
+                Cell::Space(lines.first().cloned(), lines.get(1).cloned())
+            }
+        }).collect()).collect());
+
+        match design(&drawn, &allowed) {
+            Ok(board) => {
+                let solution = sat_solutions(&board, 1).pop().ok_or(ArtErrorEnum::Design(DesignErrorEnum::NotFound))?;
+                let pzpr = encode_board(&board);
+                return Ok(Generated { board, solution, pzpr });
+            }
+            Err(DesignErrorEnum::NotForced) | Err(DesignErrorEnum::NotFound) => {}
+            Err(error) => return Err(ArtErrorEnum::Design(error)),
+        }
+
+        // 別解と食い違うループ上のマスを一つヒントにする
+        let differences = match alternative_with_all_counts(&answer) {
+            Ok(Some(other)) => answer.differences(&other),
+            _ => Vec::new(),
+        };
+        unforced = differences.iter().filter(|(i, j)| mask[*i][*j]).cloned().collect();
+        let mut candidates: Vec<usize> = differences.iter().map(|(i, j)| i * width + j).filter(|k| !answer.lines[*k].is_empty()).collect();
+        if candidates.is_empty() {
+            candidates = (0..height * width).filter(|k| !answer.lines[*k].is_empty()).collect();
+        }
+        clue[candidates[rng.below(candidates.len())]] = true;
+    }
+    Err(ArtErrorEnum::NotForced(unforced))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(rows: &[&str]) -> Vec<Vec<bool>> {
+        rows.iter().map(|row| row.chars().map(|c| c == '#').collect()).collect()
+    }
+
+    #[test]
+    fn adjacent_mask() {
+        let result = art(&mask(&["....", ".##.", "....", "...."]), 0);
+        assert_eq!(result.err(), Some(ArtErrorEnum::Adjacent(vec![(1, 1), (1, 2)])));
+    }
+
+    // 型紙どおりに黒マスが並び、解は一つだけ
+    #[test]
+    fn shaded_cells_follow_the_mask() {
+        let mask = mask(&["......", ".#..#.", "......", "......", ".#..#.", "......"]);
+        let generated = art(&mask, 0).unwrap();
+        let solutions = sat_solutions(&generated.board, 2);
+        assert_eq!(solutions.len(), 1);
+        for (i, row) in mask.iter().enumerate() {
+            for (j, shaded) in row.iter().enumerate() {
+                assert_eq!(matches!(solutions[0].0[i][j], Cell::Wall(WallEnum::Wall)), *shaded, "({}, {})", i, j);
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+use crate::{dir_to_char, Board, Cell, CheckResultInvalidEnum, DirectionEnum, WallEnum};
+use crate::cnf::SatSession;
+use crate::grid::DIRECTIONS;
+use crate::sat::Lit;
+
+// すべての解で共通しているか
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForcedEnum {
+    Clue(DirectionEnum, i64),
+    Shaded,
+    Loop(Vec<DirectionEnum>), // 線が通ることが確定。確定している線の向き
+    Undetermined,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backbone {
+    pub cells: Vec<Vec<ForcedEnum>>,
+    pub unique: bool,
+    pub solvable: bool, // 解が一つもなければfalse。このときヒント以外のマスはUndetermined
+    pub solves: usize, // SATソルバを呼んだ回数
+}
+
+impl Backbone {
+    // 確定した部分だけを書いた盤面
+    pub fn to_board(&self) -> Board {
+        Board(self.cells.iter().map(|row| row.iter().map(|forced| match forced {
+            ForcedEnum::Clue(dir, num) => Cell::Wall(WallEnum::Hint(dir.clone(), *num)),
+            ForcedEnum::Shaded => Cell::Wall(WallEnum::Wall),
+            ForcedEnum::Loop(dirs) => Cell::Space(dirs.first().cloned(), dirs.get(1).cloned()),
+            ForcedEnum::Undetermined => Cell::Unknown,
+        }).collect()).collect())
+    }
+
+    pub fn undetermined(&self) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        for (i, row) in self.cells.iter().enumerate() {
+            for (j, forced) in row.iter().enumerate() {
+                if *forced == ForcedEnum::Undetermined {
+                    result.push((i, j));
+                }
+            }
+        }
+        result
+    }
+}
+
+impl fmt::Display for Backbone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.iter() {
+            for forced in row.iter() {
+                match forced {
+                    ForcedEnum::Clue(dir, num) if *num >= 0 => write!(f, "{}{}", dir_to_char(dir.clone()), num)?,
+                    ForcedEnum::Clue(dir, _) => write!(f, "{}?", dir_to_char(dir.clone()))?,
+                    ForcedEnum::Shaded => write!(f, " W")?,
+                    ForcedEnum::Loop(dirs) => match dirs.len() {
+                        0 => write!(f, "..")?,
+                        1 => write!(f, "{}.", dir_to_char(dirs[0].clone()))?,
+                        _ => write!(f, "{}{}", dir_to_char(dirs[0].clone()), dir_to_char(dirs[1].clone()))?,
+                    },
+                    ForcedEnum::Undetermined => write!(f, "??")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// 仮定を足しながら解き直し、すべての解で値が変わらない変数を探す
+// 盤面が壊れているときはErr、解がないときはsolvableがfalseのBackbone
+pub fn backbone(board: &Board) -> Result<Backbone, CheckResultInvalidEnum> {
+    let mut session = SatSession::new(board)?;
+    let first = match session.solve(&[]) {
+        Some(first) => first,
+        None => {
+            let grid = session.cnf().grid();
+            let cells = (0..grid.height()).map(|i| (0..grid.width()).map(|j| match grid.clue(i, j) {
+                Some((dir, num)) => ForcedEnum::Clue(dir.clone(), *num),
+                None => ForcedEnum::Undetermined,
+            }).collect()).collect();
+            return Ok(Backbone { cells, unique: false, solvable: false, solves: 1 });
+        }
+    };
+    let mut solves = 1;
+
+    let cnf = session.cnf().clone();
+    let mut vars: Vec<Lit> = Vec::new();
+    for i in 0..first.height() {
+        for j in 0..first.width() {
+            vars.extend(cnf.shaded_var(i, j));
+        }
+    }
+    vars.extend(cnf.edge_vars().iter().map(|(_, v)| *v));
+
+    // 最初の解での値を候補にして、別の解が見つかったら外す
+    let value = |session: &SatSession, var: Lit| -> Lit {
+        if session.model_value(var) { var } else { -var }
+    };
+    let mut candidates: Vec<Option<Lit>> = vars.iter().map(|v| Some(value(&session, *v))).collect();
+    let mut forced: Vec<Lit> = Vec::new();
+    for k in 0..vars.len() {
+        let lit = match candidates[k] {
+            Some(lit) => lit,
+            None => continue,
+        };
+        solves += 1;
+        match session.solve(&[-lit]) {
+            Some(_) => {
+                for (l, var) in vars.iter().enumerate() {
+                    if candidates[l] == Some(-value(&session, *var)) {
+                        candidates[l] = None;
+                    }
+                }
+            }
+            None => forced.push(lit),
+        }
+    }
+    let unique = forced.len() == vars.len();
+
+    let mut cells = Vec::new();
+    for i in 0..first.height() {
+        let mut row = Vec::new();
+        for j in 0..first.width() {
+            if let Some((dir, num)) = first.clue(i, j) {
+                row.push(ForcedEnum::Clue(dir.clone(), *num));
+                continue;
+            }
+            let s = cnf.shaded_var(i, j).unwrap();
+            row.push(if forced.contains(&s) {
+                ForcedEnum::Shaded
+            } else if forced.contains(&-s) {
+                let dirs = DIRECTIONS.iter()
+                    .filter(|dir| first.edge_towards(i, j, dir).and_then(|e| cnf.edge_var(e)).map(|v| forced.contains(&v)).unwrap_or(false))
+                    .cloned()
+                    .collect();
+                ForcedEnum::Loop(dirs)
+            } else {
+                ForcedEnum::Undetermined
+            });
+        }
+        cells.push(row);
+    }
+    Ok(Backbone { cells, unique, solvable: true, solves })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::sat_solutions;
+    use crate::create_board;
+    use crate::diff::diff;
+
+    #[test]
+    fn unique_puzzle_forces_every_cell() {
+        let board = create_board("5/5/g22q");
+        let result = backbone(&board).unwrap();
+        assert!(result.solvable && result.unique);
+        assert!(result.undetermined().is_empty());
+        assert!(diff(&result.to_board(), &sat_solutions(&board, 1)[0]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn under_clued_board() {
+        let result = backbone(&create_board("3/3/")).unwrap();
+        assert!(result.solvable && !result.unique);
+        for (i, j) in [(0, 0), (0, 2), (2, 0), (2, 2)] {
+            assert_eq!(result.cells[i][j], ForcedEnum::Undetermined);
+        }
+    }
+
+    #[test]
+    fn unsatisfiable_board() {
+        // 3マスの列に黒マスは3つ置けない
+        let result = backbone(&create_board("5/5/g23q")).unwrap();
+        assert!(!result.solvable && !result.unique);
+        assert_eq!(result.cells[1][2], ForcedEnum::Clue(DirectionEnum::Down, 3));
+        assert_eq!(result.undetermined().len(), 24);
+    }
+}
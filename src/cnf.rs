@@ -0,0 +1,513 @@
+use std::collections::HashMap;
+use union_find::{QuickUnionUf, UnionBySize, UnionFind};
+use crate::{Board, CheckResultEnum, CheckResultInvalidEnum, DirectionEnum};
+use crate::grid::{CellStateEnum, EdgeEnum, EdgeStateEnum, FactEnum, Grid};
+use crate::sat::{Lit, Solver};
+
+// ヤジリンのCNF。黒マスの変数と辺の変数を持つ
+#[derive(Clone)]
+pub struct Cnf {
+    pub num_vars: usize,
+    pub clauses: Vec<Vec<Lit>>,
+    shaded: Vec<Option<Lit>>,
+    edges: Vec<(EdgeEnum, Lit)>,
+    edge_index: HashMap<EdgeEnum, Lit>,
+    grid: Grid,
+}
+
+impl Cnf {
+    pub fn new(board: &Board) -> Result<Cnf, CheckResultInvalidEnum> {
+        let grid = Grid::from_board(board)?;
+        let (height, width) = (grid.height(), grid.width());
+        let mut cnf = Cnf { num_vars: 0, clauses: Vec::new(), shaded: vec![None; height * width], edges: Vec::new(), edge_index: HashMap::new(), grid: grid.clone() };
+
+        for i in 0..height {
+            for j in 0..width {
+                if grid.cell(i, j) != CellStateEnum::Clue {
+                    cnf.shaded[i * width + j] = Some(cnf.new_var());
+                }
+            }
+        }
+        for e in grid.all_edges() {
+            let (a, b) = e.cells();
+            if cnf.shaded_var(a.0, a.1).is_some() && cnf.shaded_var(b.0, b.1).is_some() {
+                let var = cnf.new_var();
+                cnf.edges.push((e, var));
+                cnf.edge_index.insert(e, var);
+            }
+        }
+
+        for i in 0..height {
+            for j in 0..width {
+                let s = match cnf.shaded_var(i, j) {
+                    Some(s) => s,
+                    None => continue,
+                };
+                // 黒マスは隣り合わない
+                for (y, x) in [(i + 1, j), (i, j + 1)] {
+                    if y < height && x < width {
+                        if let Some(t) = cnf.shaded_var(y, x) {
+                            cnf.clauses.push(vec![-s, -t]);
+                        }
+                    }
+                }
+                // 黒マスなら線は0本、そうでなければ2本
+                let lines: Vec<Lit> = grid.edges_around(i, j).into_iter().filter_map(|e| cnf.edge_var(e)).collect();
+                cnf.clauses.extend(degree(&lines, -s));
+            }
+        }
+
+        // ヒントの数字
+        for i in 0..height {
+            for j in 0..width {
+                if let Some((dir, num)) = grid.clue(i, j) {
+                    if *dir == DirectionEnum::None || *num < 0 {
+                        continue;
+                    }
+                    let xs: Vec<Lit> = grid.ray(i, j, dir).into_iter().filter_map(|(y, x)| cnf.shaded_var(y, x)).collect();
+                    cnf.exactly(&xs, *num as usize);
+                }
+            }
+        }
+
+        // ループは空ではない
+        cnf.clauses.push(cnf.shaded.iter().flatten().map(|s| -s).collect());
+
+        // 盤面に書かれている状態
+        for fact in grid.facts() {
+            if let Some(lit) = cnf.fact_lit(fact) {
+                cnf.clauses.push(vec![lit]);
+            }
+        }
+        Ok(cnf)
+    }
+
+    fn new_var(&mut self) -> Lit {
+        self.num_vars += 1;
+        self.num_vars as Lit
+    }
+
+    pub fn exactly(&mut self, xs: &[Lit], k: usize) {
+        let mut num_vars = self.num_vars;
+        let clauses = exactly(xs, k, &mut || {
+            num_vars += 1;
+            num_vars as Lit
+        });
+        self.num_vars = num_vars;
+        self.clauses.extend(clauses);
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn shaded_var(&self, i: usize, j: usize) -> Option<Lit> {
+        self.shaded[i * self.grid.width() + j]
+    }
+
+    pub fn edge_var(&self, e: EdgeEnum) -> Option<Lit> {
+        self.edge_index.get(&e).cloned()
+    }
+
+    pub fn edge_vars(&self) -> &[(EdgeEnum, Lit)] {
+        &self.edges
+    }
+
+    pub fn fact_lit(&self, fact: FactEnum) -> Option<Lit> {
+        match fact {
+            FactEnum::Shaded(i, j) => self.shaded_var(i, j),
+            FactEnum::Unshaded(i, j) => self.shaded_var(i, j).map(|s| -s),
+            FactEnum::Line(e) => self.edge_var(e),
+            FactEnum::Cross(e) => self.edge_var(e).map(|v| -v),
+        }
+    }
+
+    // 変数の値から盤面を作る
+    pub fn decode(&self, value: impl Fn(Lit) -> bool) -> Result<Grid, CheckResultInvalidEnum> {
+        let mut grid = self.grid.clone();
+        for i in 0..grid.height() {
+            for j in 0..grid.width() {
+                if let Some(s) = self.shaded_var(i, j) {
+                    grid.apply(if value(s) { FactEnum::Shaded(i, j) } else { FactEnum::Unshaded(i, j) })?;
+                }
+            }
+        }
+        for e in grid.all_edges() {
+            let line = self.edge_var(e).map(&value).unwrap_or(false);
+            grid.apply(if line { FactEnum::Line(e) } else { FactEnum::Cross(e) })?;
+        }
+        Ok(grid)
+    }
+
+    // ループが複数に分かれていたら、それぞれを外と繋ぐ節。valueは解での値
+    pub fn loop_cuts(&self, value: impl Fn(Lit) -> bool) -> Vec<Vec<Lit>> {
+        let width = self.grid.width();
+        let edges: Vec<(usize, usize, Lit)> = self.edges.iter().map(|(e, v)| {
+            let (a, b) = e.cells();
+            (a.0 * width + a.1, b.0 * width + b.1, *v)
+        }).collect();
+        loop_cuts(self.shaded.len(), &edges, |k| self.shaded[k].map(|s| -s), value)
+    }
+
+    // 見つかった解を禁止する節
+    pub fn blocking_clause(&self, grid: &Grid) -> Vec<Lit> {
+        let mut clause = Vec::new();
+        for i in 0..grid.height() {
+            for j in 0..grid.width() {
+                if let Some(s) = self.shaded_var(i, j) {
+                    clause.push(if grid.cell(i, j) == CellStateEnum::Shaded { -s } else { s });
+                }
+            }
+        }
+        for (e, v) in self.edges.iter() {
+            clause.push(if grid.edge(*e) == EdgeStateEnum::Line { -v } else { *v });
+        }
+        clause
+    }
+}
+
+impl Cnf {
+    // DIMACS形式。ループが一つであることは含まれないので、解はcheckで確かめる
+    pub fn to_dimacs(&self) -> String {
+        let mut text = format!("p cnf {} {}\n", self.num_vars, self.clauses.len());
+        for clause in self.clauses.iter() {
+            for lit in clause {
+                text += &format!("{} ", lit);
+            }
+            text += "0\n";
+        }
+        text
+    }
+
+    // 変数番号と盤面の対応。書かれていない変数は補助変数
+    pub fn var_map(&self) -> String {
+        let mut text = format!("c {} {}\n", self.grid.width(), self.grid.height());
+        for i in 0..self.grid.height() {
+            for j in 0..self.grid.width() {
+                if let Some(s) = self.shaded_var(i, j) {
+                    text += &format!("{} shaded {} {}\n", s, i, j);
+                }
+            }
+        }
+        for (e, v) in self.edges.iter() {
+            text += &match e {
+                EdgeEnum::Right(i, j) => format!("{} right {} {}\n", v, i, j),
+                EdgeEnum::Down(i, j) => format!("{} down {} {}\n", v, i, j),
+            };
+        }
+        text
+    }
+
+    // SATソルバの出力(vの行)から盤面を作る
+    pub fn read_model(&self, output: &str) -> Result<Board, String> {
+        let model = parse_model(output)?;
+        let mut values = vec![false; self.num_vars + 1];
+        for lit in model {
+            if lit > 0 && (lit as usize) <= self.num_vars {
+                values[lit as usize] = true;
+            }
+        }
+        self.decode(|lit| if lit > 0 { values[lit as usize] } else { !values[(-lit) as usize] })
+            .map(|grid| grid.to_board())
+            .map_err(|reason| format!("{:?}", reason))
+    }
+}
+
+// onが真ならlinesのちょうど2本が線、偽なら0本。2本引けないならonは偽
+pub fn degree(lines: &[Lit], on: Lit) -> Vec<Vec<Lit>> {
+    let mut clauses = Vec::new();
+    for e in lines.iter() {
+        clauses.push(vec![on, -e]);
+    }
+    for a in 0..lines.len() {
+        for b in a + 1..lines.len() {
+            for c in b + 1..lines.len() {
+                clauses.push(vec![-lines[a], -lines[b], -lines[c]]);
+            }
+        }
+    }
+    if lines.len() < 2 {
+        clauses.push(vec![-on]);
+    } else {
+        for skip in 0..lines.len() {
+            let mut clause = vec![-on];
+            clause.extend(lines.iter().enumerate().filter(|(k, _)| *k != skip).map(|(_, e)| *e));
+            clauses.push(clause);
+        }
+    }
+    clauses
+}
+
+// 解の線が複数の成分に分かれていたら、それぞれの成分を外と繋ぐ節。一つに繋がっていれば空
+// edgesは(マス, マス, 辺の変数)、on(k)はマスkがループ上にあることを表すリテラル
+pub fn loop_cuts(cells: usize, edges: &[(usize, usize, Lit)], on: impl Fn(usize) -> Option<Lit>, value: impl Fn(Lit) -> bool) -> Vec<Vec<Lit>> {
+    let mut uf = QuickUnionUf::<UnionBySize>::new(cells);
+    for (a, b, v) in edges.iter() {
+        if value(*v) {
+            uf.union(*a, *b);
+        }
+    }
+    let mut roots: Vec<(usize, usize)> = Vec::new();
+    for k in 0..cells {
+        if on(k).map(&value).unwrap_or(false) {
+            let root = uf.find(k);
+            if !roots.iter().any(|(r, _)| *r == root) {
+                roots.push((root, k));
+            }
+        }
+    }
+    if roots.len() < 2 {
+        return vec![];
+    }
+    let mut cuts = Vec::new();
+    for (k, (root, y)) in roots.iter().enumerate() {
+        let x = roots[(k + 1) % roots.len()].1;
+        let mut clause: Vec<Lit> = edges.iter()
+            .filter(|(a, b, _)| (uf.find(*a) == *root) != (uf.find(*b) == *root))
+            .map(|(_, _, v)| *v)
+            .collect();
+        clause.extend(on(*y).map(|u| -u));
+        clause.extend(on(x).map(|u| -u));
+        cuts.push(clause);
+    }
+    cuts
+}
+
+// 逐次カウンタでちょうどk個が真になる節。補助変数はnew_varで作る
+pub fn exactly(xs: &[Lit], k: usize, new_var: &mut dyn FnMut() -> Lit) -> Vec<Vec<Lit>> {
+    if k > xs.len() {
+        return vec![vec![]];
+    }
+    if k == 0 {
+        return xs.iter().map(|x| vec![-x]).collect();
+    }
+    let mut clauses = Vec::new();
+    // r[i][j] は x[0..=i] のうち j + 1 個以上が真
+    let mut r: Vec<Vec<Lit>> = Vec::new();
+    for (i, x) in xs.iter().enumerate() {
+        let mut row = Vec::new();
+        for j in 0..=i.min(k) {
+            let v = new_var();
+            let prev = if i > 0 { r[i - 1].get(j).cloned() } else { None };
+            let prev_less = if j == 0 { None } else { Some(r[i - 1][j - 1]) };
+            if let Some(p) = prev {
+                clauses.push(vec![-p, v]);
+            }
+            match prev_less {
+                Some(q) => {
+                    clauses.push(vec![-x, -q, v]);
+                    let mut c = vec![-v, q];
+                    c.extend(prev);
+                    clauses.push(c);
+                }
+                None => clauses.push(vec![-x, v]),
+            }
+            let mut c = vec![-v, *x];
+            c.extend(prev);
+            clauses.push(c);
+            row.push(v);
+        }
+        r.push(row);
+    }
+    let last = &r[xs.len() - 1];
+    clauses.push(vec![last[k - 1]]);
+    if let Some(over) = last.get(k) {
+        clauses.push(vec![-over]);
+    }
+    clauses
+}
+
+// "v"で始まる行のリテラルを読む。"v"の行がなければ数字だけの行を読む
+pub fn parse_model(output: &str) -> Result<Vec<Lit>, String> {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut v_lines: Vec<&str> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if line.starts_with("s ") || line == "SAT" || line == "UNSAT" || line.starts_with("UNSAT") {
+            if line.contains("UNSAT") {
+                return Err("UNSATISFIABLE".to_string());
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('v') {
+            v_lines.push(rest);
+        } else if !line.is_empty() && !line.starts_with('c') {
+            lines.push(line);
+        }
+    }
+    let mut model = Vec::new();
+    for line in if v_lines.is_empty() { lines } else { v_lines } {
+        for token in line.split_whitespace() {
+            let lit: Lit = token.parse().map_err(|_| format!("invalid literal: {}", token))?;
+            if lit != 0 {
+                model.push(lit);
+            }
+        }
+    }
+    Ok(model)
+}
+
+pub fn write_dimacs(board: &Board, cnf_path: &str, map_path: &str) -> std::io::Result<()> {
+    let cnf = Cnf::new(board).map_err(|reason| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{:?}", reason)))?;
+    std::fs::write(cnf_path, cnf.to_dimacs())?;
+    std::fs::write(map_path, cnf.var_map())
+}
+
+// CNFをSATソルバで解き、ループが一つになるまで節を足していく
+pub struct SatSession {
+    cnf: Cnf,
+    solver: Solver,
+}
+
+impl SatSession {
+    pub fn new(board: &Board) -> Result<SatSession, CheckResultInvalidEnum> {
+        Ok(SatSession::from_cnf(Cnf::new(board)?))
+    }
+
+    pub fn from_cnf(cnf: Cnf) -> SatSession {
+        let mut solver = Solver::new();
+        for _ in 0..cnf.num_vars {
+            solver.new_var();
+        }
+        for clause in cnf.clauses.iter() {
+            solver.add_clause(clause);
+        }
+        SatSession { cnf, solver }
+    }
+
+    pub fn cnf(&self) -> &Cnf {
+        &self.cnf
+    }
+
+    pub fn solver(&self) -> &Solver {
+        &self.solver
+    }
+
+    pub fn add_clause(&mut self, clause: &[Lit]) -> bool {
+        self.solver.add_clause(clause)
+    }
+
+    pub fn solve(&mut self, assumptions: &[Lit]) -> Option<Grid> {
+        loop {
+            if !self.solver.solve(assumptions) {
+                return None;
+            }
+            let grid = self.cnf.decode(|lit| self.solver.model_value(lit)).ok()?;
+            let cuts = self.cnf.loop_cuts(|lit| self.solver.model_value(lit));
+            if cuts.is_empty() {
+                return Some(grid);
+            }
+            for cut in cuts {
+                self.solver.add_clause(&cut);
+            }
+        }
+    }
+
+    // 直前に見つかった解での値
+    pub fn model_value(&self, lit: Lit) -> bool {
+        self.solver.model_value(lit)
+    }
+
+    pub fn block(&mut self, grid: &Grid) -> bool {
+        let clause = self.cnf.blocking_clause(grid);
+        self.solver.add_clause(&clause)
+    }
+}
+
+pub fn sat_solutions(board: &Board, limit: usize) -> Vec<Board> {
+    let mut result = Vec::new();
+    if let Ok(mut session) = SatSession::new(board) {
+        while result.len() < limit {
+            match session.solve(&[]) {
+                Some(grid) => {
+                    result.push(grid.to_board());
+                    session.block(&grid);
+                }
+                None => break,
+            }
+        }
+    }
+    result
+}
+
+pub fn solve_sat(board: &Board) -> (CheckResultEnum, Option<Board>) {
+    match SatSession::new(board) {
+        Ok(mut session) => match session.solve(&[]) {
+            Some(grid) => (CheckResultEnum::Complete, Some(grid.to_board())),
+            None => (CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer), None),
+        },
+        Err(reason) => (CheckResultEnum::Invalid(reason), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_board;
+    use crate::diff::diff;
+
+    const PROBLEM: &str = "5/5/g22q";
+
+    #[test]
+    fn exactly_counts() {
+        for n in 1..=4 {
+            for k in 0..=n {
+                let mut solver = Solver::new();
+                let xs: Vec<Lit> = (0..n).map(|_| solver.new_var()).collect();
+                for clause in exactly(&xs, k, &mut || solver.new_var()) {
+                    solver.add_clause(&clause);
+                }
+                for bits in 0..1 << n {
+                    let assumptions: Vec<Lit> = xs.iter().enumerate().map(|(i, x)| if bits >> i & 1 == 1 { *x } else { -x }).collect();
+                    assert_eq!(solver.solve(&assumptions), (bits as u32).count_ones() as usize == k, "n={} k={} bits={:b}", n, k, bits);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dimacs_matches_clauses() {
+        let cnf = Cnf::new(&create_board(PROBLEM)).unwrap();
+        let text = cnf.to_dimacs();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some(format!("p cnf {} {}", cnf.num_vars, cnf.clauses.len()).as_str()));
+        let clauses: Vec<Vec<Lit>> = lines.map(|line| {
+            let mut lits: Vec<Lit> = line.split_whitespace().map(|t| t.parse().unwrap()).collect();
+            assert_eq!(lits.pop(), Some(0));
+            lits
+        }).collect();
+        assert_eq!(clauses, cnf.clauses);
+    }
+
+    #[test]
+    fn var_map_names_every_board_variable() {
+        let cnf = Cnf::new(&create_board(PROBLEM)).unwrap();
+        let map = cnf.var_map();
+        assert_eq!(map.lines().next(), Some("c 5 5"));
+        let shaded = (0..5).flat_map(|i| (0..5).map(move |j| (i, j))).filter(|(i, j)| cnf.shaded_var(*i, *j).is_some()).count();
+        assert_eq!(map.lines().count(), 1 + shaded + cnf.edge_vars().len());
+        assert!(map.lines().any(|line| line == format!("{} right 0 0", cnf.edge_var(EdgeEnum::Right(0, 0)).unwrap())));
+    }
+
+    // 内蔵ソルバの解をDIMACSの出力形式で書き、read_modelで読み戻す
+    #[test]
+    fn model_round_trip() {
+        let board = create_board(PROBLEM);
+        let mut session = SatSession::new(&board).unwrap();
+        let grid = session.solve(&[]).unwrap();
+        let lits: Vec<String> = (1..=session.cnf().num_vars as Lit)
+            .map(|v| if session.model_value(v) { v.to_string() } else { (-v).to_string() })
+            .collect();
+        let output = format!("c comment\ns SATISFIABLE\nv {}\nv 0\n", lits.join(" "));
+        let read = session.cnf().read_model(&output).unwrap();
+        assert!(diff(&grid.to_board(), &read).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_model_formats() {
+        assert_eq!(parse_model("s SATISFIABLE\nv 1 -2\nv 3 0\n"), Ok(vec![1, -2, 3]));
+        assert_eq!(parse_model("SAT\n-1 2 0\n"), Ok(vec![-1, 2]));
+        assert!(parse_model("s UNSATISFIABLE\n").is_err());
+        assert!(parse_model("v 1 x 0\n").is_err());
+    }
+}
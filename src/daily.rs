@@ -0,0 +1,107 @@
+use crate::generator::Generated;
+use crate::random::Rng;
+use crate::rating::{DifficultyEnum, Rating};
+use crate::symmetry::SymmetryEnum;
+use crate::target::{generate_to, BudgetEnum};
+
+pub struct Daily {
+    pub date: String, // YYYY-MM-DD
+    pub seed: u64,
+    pub generated: Generated,
+    pub rating: Rating,
+    pub hit: bool,
+}
+
+// 時間で打ち切ると実行環境で結果が変わるので、試す回数で打ち切る
+const DAILY_ATTEMPTS: usize = 20;
+const MAX_SALTS: u64 = 8;
+
+fn parse_date(date: &str) -> Result<(u32, u32, u32), String> {
+    let error = || format!("date must be YYYY-MM-DD: \"{}\"", date);
+    let parts: Vec<&str> = date.trim().split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+        return Err(error());
+    }
+    let year: u32 = parts[0].parse().map_err(|_| error())?;
+    let month: u32 = parts[1].parse().map_err(|_| error())?;
+    let day: u32 = parts[2].parse().map_err(|_| error())?;
+    let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return Err(error()),
+    };
+    if day == 0 || day > days {
+        return Err(error());
+    }
+    Ok((year, month, day))
+}
+
+// 日付と大きさと難易度から決まるseed
+// 標準のハッシュはRustの版で変わりうるので、FNV-1aで混ぜる
+pub fn daily_seed(date: &str, width: usize, height: usize, difficulty: DifficultyEnum) -> Result<u64, String> {
+    let (year, month, day) = parse_date(date)?;
+    let key = format!("yajilin-daily/{:04}-{:02}-{:02}/{}x{}/{}", year, month, day, width, height, difficulty);
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in key.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    Ok(Rng::new(hash).next_u64())
+}
+
+// その日の問題。同じ日付なら、どの環境で何度実行しても同じ問題になる
+// 作れなかったときは、seedをずらして作り直す
+pub fn daily(date: &str, size: (usize, usize), difficulty: DifficultyEnum) -> Result<Daily, String> {
+    let (width, height) = size;
+    let seed = daily_seed(date, width, height, difficulty)?;
+    let (year, month, day) = parse_date(date)?;
+    for salt in 0..MAX_SALTS {
+        let salted = seed.wrapping_add(salt);
+        if let Some(targeted) = generate_to(width, height, salted, difficulty, SymmetryEnum::Rotational180, BudgetEnum::Attempts(DAILY_ATTEMPTS)) {
+            return Ok(Daily {
+                date: format!("{:04}-{:02}-{:02}", year, month, day),
+                seed: salted,
+                generated: targeted.generated,
+                rating: targeted.rating,
+                hit: targeted.hit,
+            });
+        }
+    }
+    Err(format!("cannot generate a {}x{} puzzle for {}", width, height, date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("2024-02-29"), Ok((2024, 2, 29)));
+        assert_eq!(parse_date("2000-02-29"), Ok((2000, 2, 29)));
+        assert!(parse_date("2026-02-30").is_err());
+        assert!(parse_date("1900-02-29").is_err());
+        assert!(parse_date("2026-2-01").is_err());
+        assert!(parse_date("2026-13-01").is_err());
+        assert!(parse_date("2026-04-31").is_err());
+    }
+
+    // 版や環境が変わってもseedは変わらない
+    #[test]
+    fn pinned_seed() {
+        assert_eq!(daily_seed("2026-03-14", 10, 10, DifficultyEnum::Medium), Ok(14784428771026947582));
+        assert_ne!(daily_seed("2026-03-14", 10, 10, DifficultyEnum::Hard), daily_seed("2026-03-14", 10, 10, DifficultyEnum::Medium));
+        assert!(daily_seed("2026-02-30", 10, 10, DifficultyEnum::Medium).is_err());
+    }
+
+    #[test]
+    fn same_puzzle_every_time() {
+        let first = daily("2026-03-14", (6, 6), DifficultyEnum::Easy).unwrap();
+        let second = daily("2026-03-14", (6, 6), DifficultyEnum::Easy).unwrap();
+        assert_eq!(first.date, "2026-03-14");
+        assert_eq!(first.generated.pzpr, second.generated.pzpr);
+        assert_eq!(first.seed, second.seed);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{check, Board, Cell, CheckResultEnum, CheckResultInvalidEnum, DirectionEnum, WallEnum};
+use crate::cnf::{sat_solutions, Cnf, SatSession};
+use crate::generator::Answer;
+use crate::grid::DIRECTIONS;
+use crate::sat::{Lit, Solver};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DesignErrorEnum {
+    Invalid(CheckResultInvalidEnum), // 描かれた答えがルールを満たしていない
+    NotAllowed(Vec<(usize, usize)>), // ヒントになるのに、allowedで許されていないマス
+    NotForced,                       // すべてのヒントが四方向の数を示しても別解がある
+    NotFound,                        // ただ一つの解になる向きの組み合わせが存在しない
+}
+
+// 線も黒マスもないマスを、向きのないヒントにした盤面
+fn with_blank_clues(answer: &Board) -> Board {
+    Board(answer.0.iter().map(|row| row.iter().map(|cell| match cell {
+        Cell::Space(Some(_), _) | Cell::Wall(_) => cell.clone(),
+        _ => Cell::Wall(WallEnum::Hint(DirectionEnum::None, -2)),
+    }).collect()).collect())
+}
+
+// ヒントのマスが四方向すべての数を示すとしても残る別解。解が一つに決まるならNone
+pub fn alternative_with_all_counts(answer: &Answer) -> Result<Option<Board>, CheckResultInvalidEnum> {
+    let mut blank = answer.clone();
+    for clue in blank.clues.iter_mut().flatten() {
+        *clue = DirectionEnum::None;
+    }
+    let mut cnf = Cnf::new(&blank.puzzle())?;
+    for k in 0..answer.height * answer.width {
+        if answer.clues[k].is_none() {
+            continue;
+        }
+        let (i, j) = (k / answer.width, k % answer.width);
+        for dir in DIRECTIONS.iter() {
+            let xs: Vec<i32> = answer.ray(i, j, dir).iter().filter_map(|(y, x)| cnf.shaded_var(*y, *x)).collect();
+            cnf.exactly(&xs, answer.count(i, j, dir) as usize);
+        }
+    }
+    let mut session = SatSession::from_cnf(cnf);
+    match session.solve(&[]) {
+        Some(grid) => {
+            session.block(&grid);
+            Ok(session.solve(&[]).map(|other| other.to_board()))
+        }
+        None => Err(CheckResultInvalidEnum::NoAnswer),
+    }
+}
+
+// 描かれた答えがただ一つの解になるように、ヒントの向きを選ぶ
+// 線も黒マスもないマスと、もとからあるヒントのマスがヒントになる。allowedで許されたマスにしか置かない
+// もとからあるヒントの向きと数も選び直す
+pub fn design(answer: &Board, allowed: &[Vec<bool>]) -> Result<Board, DesignErrorEnum> {
+    let solution = with_blank_clues(answer);
+    match check(&solution) {
+        CheckResultEnum::Complete => {}
+        CheckResultEnum::Invalid(reason) => return Err(DesignErrorEnum::Invalid(reason)),
+        CheckResultEnum::Valid => return Err(DesignErrorEnum::Invalid(CheckResultInvalidEnum::NoAnswer)),
+    }
+    let mut target = Answer::from_solution(&solution);
+    let not_allowed: Vec<(usize, usize)> = (0..target.height * target.width)
+        .filter(|k| matches!(answer.0[k / target.width][k % target.width], Cell::Unknown | Cell::Space(None, _) | Cell::Wall(WallEnum::Hint(..))))
+        .map(|k| (k / target.width, k % target.width))
+        .filter(|(i, j)| !allowed.get(*i).and_then(|row| row.get(*j)).cloned().unwrap_or(false))
+        .collect();
+    if !not_allowed.is_empty() {
+        return Err(DesignErrorEnum::NotAllowed(not_allowed));
+    }
+    if !matches!(alternative_with_all_counts(&target), Ok(None)) {
+        return Err(DesignErrorEnum::NotForced);
+    }
+
+    // 向きの選び方をSATで探す。変数はヒントのマスとその向きの組で、各マスちょうど一つ
+    // 別解が見つかるたびに、その別解と数が食い違う向きのどれかを選ぶ節を足す
+    // 別解は有限なので必ず終わり、充足できなくなったら向きの組み合わせは存在しない
+    let clue_cells: Vec<usize> = (0..target.height * target.width).filter(|k| target.clues[*k].is_some()).collect();
+    let mut solver = Solver::new();
+    let mut choices: Vec<(usize, DirectionEnum, Lit)> = Vec::new();
+    for k in clue_cells.iter() {
+        let (i, j) = (k / target.width, k % target.width);
+        let longest = DIRECTIONS.iter().max_by_key(|dir| target.ray(i, j, dir).len()).unwrap();
+        let xs: Vec<Lit> = DIRECTIONS.iter().map(|dir| {
+            let x = solver.new_var();
+            choices.push((*k, dir.clone(), x));
+            x
+        }).collect();
+        // 一番長く見渡せる向きから試す
+        for (dir, x) in DIRECTIONS.iter().zip(xs.iter()) {
+            solver.set_phase(if dir == longest { *x } else { -x });
+        }
+        solver.add_clause(&xs);
+        for a in 0..xs.len() {
+            for b in a + 1..xs.len() {
+                solver.add_clause(&[-xs[a], -xs[b]]);
+            }
+        }
+    }
+    loop {
+        if !solver.solve(&[]) {
+            return Err(DesignErrorEnum::NotFound);
+        }
+        for (k, dir, x) in choices.iter() {
+            if solver.model_value(*x) {
+                target.clues[*k] = Some(dir.clone());
+            }
+        }
+        let solutions = sat_solutions(&target.puzzle(), 2);
+        let other = match solutions.iter().find(|other| !target.differences(other).is_empty()) {
+            Some(other) => Answer::from_solution(other),
+            None if solutions.is_empty() => return Err(DesignErrorEnum::NotFound),
+            None => return Ok(target.puzzle()),
+        };
+        let clause: Vec<Lit> = choices.iter()
+            .filter(|(k, dir, _)| other.count(k / target.width, k % target.width, dir) != target.count(k / target.width, k % target.width, dir))
+            .map(|(_, _, x)| *x)
+            .collect();
+        solver.add_clause(&clause);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_board;
+    use crate::diff::diff;
+    use crate::format::parse_text;
+
+    fn allow(height: usize, width: usize, value: bool) -> Vec<Vec<bool>> {
+        vec![vec![value; width]; height]
+    }
+
+    #[test]
+    fn designed_puzzle_reproduces_the_answer() {
+        let answer = sat_solutions(&create_board("5/5/g22q"), 1).pop().unwrap();
+        let puzzle = design(&answer, &allow(5, 5, true)).unwrap();
+        let solutions = sat_solutions(&puzzle, 2);
+        assert_eq!(solutions.len(), 1);
+        let difference = diff(&solutions[0], &answer).unwrap();
+        assert!(difference.shading.is_empty() && difference.edges.is_empty());
+    }
+
+    #[test]
+    fn clue_outside_allowed_cells() {
+        let answer = sat_solutions(&create_board("5/5/g22q"), 1).pop().unwrap();
+        let mut allowed = allow(5, 5, true);
+        allowed[1][2] = false;
+        assert_eq!(design(&answer, &allowed).err(), Some(DesignErrorEnum::NotAllowed(vec![(1, 2)])));
+    }
+
+    // ヒントのない4x4を一周するループは、ほかにも引き方がある
+    #[test]
+    fn loop_without_clues_is_not_forced() {
+        let answer = parse_text("↓→←→←→←↓\n↑↓→↓←→↑←\n↑↓↑→←→←↓\n↑→←→←→↑←\n").unwrap();
+        assert_eq!(design(&answer, &allow(4, 4, true)).err(), Some(DesignErrorEnum::NotForced));
+    }
+}
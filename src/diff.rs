@@ -0,0 +1,235 @@
+use std::fmt::Write;
+use crate::{Board, Cell, DirectionEnum, WallEnum};
+use crate::grid::EdgeEnum;
+use crate::render::{layout_highlighted, neighbour, RenderOptions, ShapeEnum};
+use crate::svg::scene_to_svg;
+use crate::terminal::{to_terminal, TerminalOptions};
+
+// 辺がどちらの盤面にだけあるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SideEnum {
+    First,
+    Second,
+}
+
+// 同じ問題の二つの盤面の違い
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoardDiff {
+    pub shading: Vec<(usize, usize)>,      // 黒マスか、黒マスでないか、未定かが違うマス
+    pub edges: Vec<(EdgeEnum, SideEnum)>,  // 片方だけにある線
+    pub clues: Vec<(usize, usize)>,        // ヒントの有無か中身が違うマス
+}
+
+impl BoardDiff {
+    pub fn is_empty(&self) -> bool {
+        self.shading.is_empty() && self.edges.is_empty() && self.clues.is_empty()
+    }
+
+    // 違いのあるマス。線は両端のマスを含める
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        let mut cells: Vec<(usize, usize)> = self.shading.iter().chain(self.clues.iter()).cloned().collect();
+        for (e, _) in self.edges.iter() {
+            let (a, b) = e.cells();
+            cells.push(a);
+            cells.push(b);
+        }
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+}
+
+fn size_of(board: &Board) -> (usize, usize) {
+    let height = board.0.len();
+    (height, if height == 0 { 0 } else { board.0[0].len() })
+}
+
+// どちらかの端のマスが線を出していれば、線があるとみなす
+fn edges(board: &Board) -> Vec<EdgeEnum> {
+    let (height, width) = size_of(board);
+    let mut result = Vec::new();
+    for (i, row) in board.0.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            if let Cell::Space(one, another) = cell {
+                for dir in one.iter().chain(another.iter()) {
+                    if let Some((y, x)) = neighbour(i, j, dir, height, width) {
+                        let (a, b) = (i, j).min((y, x));
+                        result.push(if a == i.max(y) { EdgeEnum::Right(a, b) } else { EdgeEnum::Down(a, b) });
+                    }
+                }
+            }
+        }
+    }
+    result.sort();
+    result.dedup();
+    result
+}
+
+// 黒マスならSome(true)、ヒントや白マスならSome(false)、未定ならNone
+// 未定のマスは、どちらかに決まっているマスとは違うものとして数える
+fn shading(cell: &Cell) -> Option<bool> {
+    match cell {
+        Cell::Wall(WallEnum::Wall) => Some(true),
+        Cell::Wall(WallEnum::Hint(..)) | Cell::Space(..) => Some(false),
+        Cell::Unknown => None,
+    }
+}
+
+fn clue(cell: &Cell) -> Option<(DirectionEnum, i64)> {
+    match cell {
+        Cell::Wall(WallEnum::Hint(dir, num)) => Some((dir.clone(), *num)),
+        _ => None,
+    }
+}
+
+pub fn diff(first: &Board, second: &Board) -> Result<BoardDiff, String> {
+    let (size, other) = (size_of(first), size_of(second));
+    if size != other || first.0.iter().zip(second.0.iter()).any(|(a, b)| a.len() != b.len()) {
+        return Err(format!("boards differ in size: {}x{} and {}x{}", size.1, size.0, other.1, other.0));
+    }
+    let mut result = BoardDiff::default();
+    for (i, (a_row, b_row)) in first.0.iter().zip(second.0.iter()).enumerate() {
+        for (j, (a, b)) in a_row.iter().zip(b_row.iter()).enumerate() {
+            if shading(a) != shading(b) {
+                result.shading.push((i, j));
+            }
+            if clue(a) != clue(b) {
+                result.clues.push((i, j));
+            }
+        }
+    }
+    let (a, b) = (edges(first), edges(second));
+    result.edges.extend(a.iter().filter(|e| !b.contains(e)).map(|e| (*e, SideEnum::First)));
+    result.edges.extend(b.iter().filter(|e| !a.contains(e)).map(|e| (*e, SideEnum::Second)));
+    result.edges.sort_by_key(|(e, _)| *e);
+    Ok(result)
+}
+
+fn describe(board: &Board, i: usize, j: usize) -> String {
+    match &board.0[i][j] {
+        Cell::Wall(WallEnum::Wall) => "shaded".to_string(),
+        Cell::Wall(WallEnum::Hint(dir, num)) => format!("clue {:?} {}", dir, if *num < 0 { "?".to_string() } else { num.to_string() }),
+        Cell::Space(Some(_), _) => "on the loop".to_string(),
+        Cell::Space(None, _) => "unshaded".to_string(),
+        Cell::Unknown => "unknown".to_string(),
+    }
+}
+
+// 違いを一行ずつ書く
+pub fn to_text(first: &Board, second: &Board, diff: &BoardDiff) -> String {
+    if diff.is_empty() {
+        return "no differences\n".to_string();
+    }
+    let mut out = String::new();
+    for (i, j) in diff.shading.iter() {
+        writeln!(out, "shading ({},{}): {} / {}", i, j, describe(first, *i, *j), describe(second, *i, *j)).unwrap();
+    }
+    for (e, side) in diff.edges.iter() {
+        writeln!(out, "line {}: only in {}", e, if *side == SideEnum::First { "first" } else { "second" }).unwrap();
+    }
+    for (i, j) in diff.clues.iter() {
+        writeln!(out, "clue ({},{}): {} / {}", i, j, describe(first, *i, *j), describe(second, *i, *j)).unwrap();
+    }
+    out
+}
+
+// 二つの盤面を横に並べ、違うマスに色を付ける。色を使えないときは'!'を付ける
+pub fn to_ansi(first: &Board, second: &Board, diff: &BoardDiff, options: &TerminalOptions) -> String {
+    let options = TerminalOptions { highlights: diff.cells(), check: false, ..options.clone() };
+    let left = to_terminal(first, &options);
+    let right = to_terminal(second, &options);
+    // 色の指定を除いた幅で揃える
+    let visible = |line: &str| {
+        let mut count = 0;
+        let mut escape = false;
+        for c in line.chars() {
+            match c {
+                '\x1b' => escape = true,
+                'm' if escape => escape = false,
+                _ if !escape => count += 1,
+                _ => {}
+            }
+        }
+        count
+    };
+    let width = left.lines().map(visible).max().unwrap_or(0);
+    let mut out = String::new();
+    for (a, b) in left.lines().zip(right.lines()) {
+        writeln!(out, "{}{}   {}", a, " ".repeat(width - visible(a)), b).unwrap();
+    }
+    out
+}
+
+// 一つ目の盤面の上に違いを重ねる。違うマスを塗り、一つ目だけの線を赤、二つ目だけの線を青で描く
+pub fn to_svg(first: &Board, diff: &BoardDiff, options: &RenderOptions) -> String {
+    let mut scene = layout_highlighted(first, options, &diff.cells());
+    let size = options.cell_size;
+    let center = |(i, j): (usize, usize)| (size * 0.5 + (j as f64 + 0.5) * size, size * 0.5 + (i as f64 + 0.5) * size);
+    for (e, side) in diff.edges.iter() {
+        let (a, b) = e.cells();
+        let ((x1, y1), (x2, y2)) = (center(a), center(b));
+        let color = if *side == SideEnum::First { (220, 40, 40) } else { (40, 80, 230) };
+        scene.shapes.push(ShapeEnum::Line { x1, y1, x2, y2, width: size * 0.16, color });
+    }
+    scene_to_svg(&scene)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::sat_solutions;
+    use crate::create_board;
+
+    #[test]
+    fn same_board_has_no_differences() {
+        let puzzle = create_board("5/5/g22q");
+        let solution = sat_solutions(&puzzle, 1).pop().unwrap();
+        for board in [&puzzle, &solution] {
+            let result = diff(board, board).unwrap();
+            assert!(result.is_empty());
+            assert_eq!(to_text(board, board, &result), "no differences\n");
+        }
+    }
+
+    #[test]
+    fn unknown_cells_differ_from_decided_ones() {
+        let puzzle = create_board("5/5/g22q");
+        let solution = sat_solutions(&puzzle, 1).pop().unwrap();
+        let result = diff(&puzzle, &solution).unwrap();
+        let unknown: Vec<(usize, usize)> = (0..25).map(|k| (k / 5, k % 5)).filter(|(i, j)| puzzle.0[*i][*j] == Cell::Unknown).collect();
+        assert_eq!(result.shading, unknown);
+        assert!(result.clues.is_empty());
+        assert!(result.edges.iter().all(|(_, side)| *side == SideEnum::Second));
+    }
+
+    #[test]
+    fn shading_lines_and_clues() {
+        let first = Board(vec![
+            vec![Cell::Wall(WallEnum::Wall), Cell::Space(Some(DirectionEnum::Right), None), Cell::Space(Some(DirectionEnum::Left), None)],
+            vec![Cell::Wall(WallEnum::Hint(DirectionEnum::Up, 1)), Cell::Space(None, None), Cell::Space(None, None)],
+        ]);
+        let second = Board(vec![
+            vec![Cell::Space(Some(DirectionEnum::Down), None), Cell::Space(Some(DirectionEnum::Right), None), Cell::Space(Some(DirectionEnum::Left), None)],
+            vec![Cell::Wall(WallEnum::Hint(DirectionEnum::Up, 0)), Cell::Space(None, None), Cell::Space(None, None)],
+        ]);
+        let result = diff(&first, &second).unwrap();
+        assert_eq!(result.shading, vec![(0, 0)]);
+        assert_eq!(result.clues, vec![(1, 0)]);
+        assert_eq!(result.edges, vec![(EdgeEnum::Down(0, 0), SideEnum::Second)]);
+        assert_eq!(result.cells(), vec![(0, 0), (1, 0)]);
+        assert!(diff(&first, &Board(vec![vec![Cell::Unknown; 3]])).is_err());
+    }
+
+    #[test]
+    fn ansi_without_color_marks_cells() {
+        let first = create_board("5/5/g22q");
+        let mut second = first.clone();
+        second.0[4][4] = Cell::Wall(WallEnum::Wall);
+        let result = diff(&first, &second).unwrap();
+        let options = TerminalOptions { color: false, ..TerminalOptions::default() };
+        let out = to_ansi(&first, &second, &result, &options);
+        assert!(!out.contains('\x1b'));
+        assert_eq!(out.lines().count(), 5);
+        assert_eq!(out.lines().last().unwrap().matches('!').count(), 2);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{dir_to_direction_enum, Board, Cell, DirectionEnum, WallEnum};
+
+fn char_to_direction_enum(c: char) -> Option<DirectionEnum> {
+    match c {
+        '↑' => Some(DirectionEnum::Up),
+        '↓' => Some(DirectionEnum::Down),
+        '←' => Some(DirectionEnum::Left),
+        '→' => Some(DirectionEnum::Right),
+        ' ' => Some(DirectionEnum::None),
+        _ => None,
+    }
+}
+
+// Boardを表示した文字列を読む
+pub fn parse_text(text: &str) -> Result<Board, String> {
+    let mut rows: Vec<Vec<Cell>> = Vec::new();
+    for (line_number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let chars: Vec<char> = line.chars().collect();
+        let mut row = Vec::new();
+        let mut p = 0;
+        while p + 1 < chars.len() {
+            let (c, next) = (chars[p], chars[p + 1]);
+            let error = || format!("line {}: cannot read \"{}{}\"", line_number + 1, c, next);
+            match (c, next) {
+                (' ', 'W') => {
+                    row.push(Cell::Wall(WallEnum::Wall));
+                    p += 2;
+                }
+                ('?', '?') => {
+                    row.push(Cell::Unknown);
+                    p += 2;
+                }
+                ('.', '.') => {
+                    row.push(Cell::Space(None, None));
+                    p += 2;
+                }
+                (_, '0'..='9') | (_, '-') => {
+                    let dir = char_to_direction_enum(c).ok_or_else(error)?;
+                    let mut end = p + 2;
+                    while end < chars.len() && chars[end].is_ascii_digit() {
+                        end += 1;
+                    }
+                    let num: i64 = chars[p + 1..end].iter().collect::<String>().parse().map_err(|_| error())?;
+                    row.push(Cell::Wall(WallEnum::Hint(dir, num)));
+                    p = end;
+                }
+                (_, '?') => {
+                    let one = char_to_direction_enum(c).filter(|dir| *dir != DirectionEnum::None).ok_or_else(error)?;
+                    row.push(Cell::Space(Some(one), None));
+                    p += 2;
+                }
+                _ => {
+                    let one = char_to_direction_enum(c).filter(|dir| *dir != DirectionEnum::None).ok_or_else(error)?;
+                    let another = char_to_direction_enum(next).filter(|dir| *dir != DirectionEnum::None).ok_or_else(error)?;
+                    row.push(Cell::Space(Some(one), Some(another)));
+                    p += 2;
+                }
+            }
+        }
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(format!("line {}: expected {} cells, found {}", line_number + 1, first.len(), row.len()));
+            }
+        }
+        rows.push(row);
+    }
+    Ok(Board(rows))
+}
+
+// pzprv3のファイル形式を読む。ヒント、黒マス、線の順に並んでいる
+pub fn parse_pzprv3(text: &str) -> Result<Board, String> {
+    let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+    match lines.next() {
+        Some(header) if header.starts_with("pzprv3") => {}
+        _ => return Err("missing pzprv3 header".to_string()),
+    }
+    match lines.next() {
+        Some("yajilin") => {}
+        other => return Err(format!("not a yajilin file: {:?}", other)),
+    }
+    let height: usize = lines.next().and_then(|line| line.parse().ok()).ok_or("cannot read the number of rows")?;
+    let width: usize = lines.next().and_then(|line| line.parse().ok()).ok_or("cannot read the number of columns")?;
+    if height == 0 || width == 0 {
+        return Err("empty board".to_string());
+    }
+    let mut tokens = lines.flat_map(|line| line.split_whitespace());
+    let mut next = |what: &str| tokens.next().ok_or(format!("file ends while reading {}", what));
+
+    let mut board = Board(vec![vec![Cell::Unknown; width]; height]);
+    let mut lines: Vec<Vec<DirectionEnum>> = vec![Vec::new(); height * width];
+    for i in 0..height {
+        for j in 0..width {
+            let token = next("clues")?;
+            if token == "." {
+                continue;
+            }
+            let (dir, num) = token.split_once(',').ok_or(format!("cannot read clue \"{}\"", token))?;
+            let dir: u32 = dir.parse().map_err(|_| format!("cannot read clue \"{}\"", token))?;
+            let num: i64 = if num == "?" { -2 } else { num.parse().map_err(|_| format!("cannot read clue \"{}\"", token))? };
+            board.0[i][j] = Cell::Wall(WallEnum::Hint(dir_to_direction_enum(dir), num));
+        }
+    }
+    for i in 0..height {
+        for j in 0..width {
+            let token = next("shaded cells")?;
+            if token == "#" {
+                board.0[i][j] = Cell::Wall(WallEnum::Wall);
+            } else if token == "+" {
+                board.0[i][j] = Cell::Space(None, None);
+            }
+        }
+    }
+    for i in 0..height {
+        for j in 0..width - 1 {
+            if next("lines")? == "1" {
+                lines[i * width + j].push(DirectionEnum::Right);
+                lines[i * width + j + 1].push(DirectionEnum::Left);
+            }
+        }
+    }
+    for i in 0..height - 1 {
+        for j in 0..width {
+            if next("lines")? == "1" {
+                lines[i * width + j].push(DirectionEnum::Down);
+                lines[(i + 1) * width + j].push(DirectionEnum::Up);
+            }
+        }
+    }
+    for (k, dirs) in lines.into_iter().enumerate() {
+        if !dirs.is_empty() {
+            board.0[k / width][k % width] = Cell::Space(dirs.first().cloned(), dirs.get(1).cloned());
+        }
+    }
+    Ok(board)
+}
+
+// 黒マスの型紙を読む。PBM(P1かP4)か、'#'を黒マスとする文字列
+pub fn parse_mask(data: &[u8]) -> Result<Vec<Vec<bool>>, String> {
+    if data.starts_with(b"P1") || data.starts_with(b"P4") {
+        return parse_pbm(data);
+    }
+    let text = std::str::from_utf8(data).map_err(|_| "mask is neither PBM nor text".to_string())?;
+    let rows: Vec<Vec<bool>> = text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().map(|c| c == '#').collect())
+        .collect();
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if width == 0 {
+        return Err("empty mask".to_string());
+    }
+    Ok(rows.into_iter().map(|mut row| {
+        row.resize(width, false);
+        row
+    }).collect())
+}
+
+fn parse_pbm(data: &[u8]) -> Result<Vec<Vec<bool>>, String> {
+    // ヘッダは空白区切りで、#から行末まではコメント
+    let mut p = 2;
+    let mut header = Vec::new();
+    while header.len() < 2 {
+        while p < data.len() && (data[p].is_ascii_whitespace() || data[p] == b'#') {
+            if data[p] == b'#' {
+                while p < data.len() && data[p] != b'\n' {
+                    p += 1;
+                }
+            } else {
+                p += 1;
+            }
+        }
+        let start = p;
+        while p < data.len() && data[p].is_ascii_digit() {
+            p += 1;
+        }
+        let value: usize = std::str::from_utf8(&data[start..p]).ok().and_then(|s| s.parse().ok()).ok_or("cannot read the PBM size")?;
+        header.push(value);
+    }
+    let (width, height) = (header[0], header[1]);
+    let mut rows = vec![vec![false; width]; height];
+    if data[1] == b'4' {
+        p += 1;
+        let stride = width.div_ceil(8);
+        if data.len() < p + stride * height {
+            return Err("PBM data is too short".to_string());
+        }
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = data[p + i * stride + j / 8] & (0x80 >> (j % 8)) != 0;
+            }
+        }
+    } else {
+        let mut bits = data[p..].iter().filter(|c| **c == b'0' || **c == b'1');
+        for row in rows.iter_mut() {
+            for cell in row.iter_mut() {
+                *cell = *bits.next().ok_or("PBM data is too short")? == b'1';
+            }
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check, create_board, solve_with, BackendEnum, CheckResultEnum};
+
+    #[test]
+    fn text_round_trip() {
+        let board = create_board("5/5/g22q");
+        let (_, solution) = solve_with(&board, BackendEnum::Sat);
+        for board in [board, solution.unwrap()] {
+            let text = board.to_string();
+            assert_eq!(parse_text(&text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn text_partial_cells() {
+        let board = parse_text("→2↓?\n..??\n").unwrap();
+        assert_eq!(board.0[0][0], Cell::Wall(WallEnum::Hint(DirectionEnum::Right, 2)));
+        assert!(matches!(board.0[0][1], Cell::Space(Some(DirectionEnum::Down), None)));
+        assert!(matches!(board.0[1][0], Cell::Space(None, None)));
+        assert_eq!(board.0[1][1], Cell::Unknown);
+    }
+
+    #[test]
+    fn text_errors() {
+        assert!(parse_text("....\n..\n").is_err());
+        assert!(parse_text("x?\n").is_err());
+    }
+
+    // 2x3の盤面。上の行はヒント、黒マス、ヒントで、下の行を線が横切る
+    #[test]
+    fn pzprv3() {
+        let text = "pzprv3\nyajilin\n2\n3\n1,3 . 0,?\n. . .\n. # .\n+ + .\n0 0\n1 1\n0 0 0\n";
+        let board = parse_pzprv3(text).unwrap();
+        assert_eq!(board.0[0][0], Cell::Wall(WallEnum::Hint(DirectionEnum::Up, 3)));
+        assert_eq!(board.0[0][1], Cell::Wall(WallEnum::Wall));
+        assert_eq!(board.0[0][2], Cell::Wall(WallEnum::Hint(DirectionEnum::None, -2)));
+        assert!(matches!(board.0[1][0], Cell::Space(Some(DirectionEnum::Right), None)));
+        assert!(matches!(board.0[1][1], Cell::Space(Some(DirectionEnum::Left), Some(DirectionEnum::Right))));
+        assert!(matches!(board.0[1][2], Cell::Space(Some(DirectionEnum::Left), None)));
+    }
+
+    #[test]
+    fn pzprv3_complete_board() {
+        let text = "pzprv3\nyajilin\n2\n2\n. .\n. .\n+ +\n+ +\n1\n1\n1 1\n";
+        let board = parse_pzprv3(text).unwrap();
+        assert!(matches!(check(&board), CheckResultEnum::Complete));
+    }
+
+    #[test]
+    fn pzprv3_errors() {
+        assert!(parse_pzprv3("yajilin\n2\n2\n").is_err());
+        assert!(parse_pzprv3("pzprv3\nslither\n2\n2\n").is_err());
+        assert!(parse_pzprv3("pzprv3\nyajilin\n2\n2\n. .\n. .\n").is_err());
+        assert!(parse_pzprv3("pzprv3\nyajilin\n1\n1\nx\n.\n").is_err());
+    }
+
+    #[test]
+    fn mask_text() {
+        let mask = parse_mask(b"#..\n\n.#\n").unwrap();
+        assert_eq!(mask, vec![vec![true, false, false], vec![false, true, false]]);
+        assert!(parse_mask(b"\n\n").is_err());
+    }
+
+    #[test]
+    fn mask_pbm_plain() {
+        let mask = parse_mask(b"P1\n# comment\n3 2\n1 0 0\n0 1 0\n").unwrap();
+        assert_eq!(mask, vec![vec![true, false, false], vec![false, true, false]]);
+        assert!(parse_mask(b"P1\n3 2\n1 0 0\n").is_err());
+    }
+
+    // 幅10なら1行2バイト
+    #[test]
+    fn mask_pbm_raw() {
+        let mut data = b"P4\n10 2\n".to_vec();
+        data.extend([0b1000_0000, 0b0100_0000, 0b0000_0001, 0b0000_0000]);
+        let mask = parse_mask(&data).unwrap();
+        assert_eq!(mask[0].iter().map(|b| *b as u8).collect::<Vec<_>>(), vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(mask[1].iter().map(|b| *b as u8).collect::<Vec<_>>(), vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0]);
+        assert!(parse_mask(&data[..data.len() - 1]).is_err());
+    }
+}
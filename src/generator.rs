@@ -0,0 +1,378 @@
+use crate::{encode_board, Board, Cell, DirectionEnum, WallEnum};
+use crate::cnf::sat_solutions;
+use crate::grid::{Grid, DIRECTIONS};
+use crate::random::Rng;
+use crate::symmetry::SymmetryEnum;
+
+pub struct Generated {
+    pub board: Board,
+    pub solution: Board,
+    pub pzpr: String,
+}
+
+const MAX_ATTEMPTS: usize = 50;
+
+// 答えの盤面。ヒントのマスは向きだけ決めておき、数字は黒マスから数える
+#[derive(Clone)]
+pub struct Answer {
+    pub height: usize,
+    pub width: usize,
+    pub lines: Vec<Vec<DirectionEnum>>, // マスから出ている線の向き
+    pub shaded: Vec<bool>,
+    pub clues: Vec<Option<DirectionEnum>>,
+}
+
+impl Answer {
+    pub fn from_solution(board: &Board) -> Answer {
+        let height = board.0.len();
+        let width = if height == 0 { 0 } else { board.0[0].len() };
+        let mut answer = Answer { height, width, lines: vec![Vec::new(); height * width], shaded: vec![false; height * width], clues: vec![None; height * width] };
+        for (k, cell) in board.0.iter().flatten().enumerate() {
+            match cell {
+                Cell::Wall(WallEnum::Hint(dir, _)) => answer.clues[k] = Some(dir.clone()),
+                Cell::Wall(WallEnum::Wall) => answer.shaded[k] = true,
+                Cell::Space(one, another) => answer.lines[k] = one.iter().chain(another.iter()).cloned().collect(),
+                Cell::Unknown => {}
+            }
+        }
+        answer
+    }
+
+    pub fn on_loop(&self, i: usize, j: usize) -> bool {
+        !self.lines[i * self.width + j].is_empty()
+    }
+
+    pub fn ray(&self, i: usize, j: usize, dir: &DirectionEnum) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        let (mut y, mut x) = (i as i32, j as i32);
+        loop {
+            match dir {
+                DirectionEnum::Up => y -= 1,
+                DirectionEnum::Down => y += 1,
+                DirectionEnum::Left => x -= 1,
+                DirectionEnum::Right => x += 1,
+                DirectionEnum::None => return result,
+            }
+            if y < 0 || x < 0 || y >= self.height as i32 || x >= self.width as i32 {
+                return result;
+            }
+            result.push((y as usize, x as usize));
+        }
+    }
+
+    pub fn count(&self, i: usize, j: usize, dir: &DirectionEnum) -> i64 {
+        self.ray(i, j, dir).iter().filter(|(y, x)| self.shaded[y * self.width + x]).count() as i64
+    }
+
+    pub fn puzzle(&self) -> Board {
+        Board((0..self.height).map(|i| (0..self.width).map(|j| match &self.clues[i * self.width + j] {
+            Some(DirectionEnum::None) => Cell::Wall(WallEnum::Hint(DirectionEnum::None, -2)),
+            Some(dir) => Cell::Wall(WallEnum::Hint(dir.clone(), self.count(i, j, dir))),
+            None => Cell::Unknown,
+        }).collect()).collect())
+    }
+
+    pub fn solution(&self) -> Board {
+        let mut board = self.puzzle();
+        for i in 0..self.height {
+            for j in 0..self.width {
+                let lines = &self.lines[i * self.width + j];
+                if self.shaded[i * self.width + j] {
+                    board.0[i][j] = Cell::Wall(WallEnum::Wall);
+                } else if !lines.is_empty() {
+                    board.0[i][j] = Cell::Space(lines.first().cloned(), lines.get(1).cloned());
+                }
+            }
+        }
+        board
+    }
+
+    // 別解と食い違うマス
+    pub fn differences(&self, other: &Board) -> Vec<(usize, usize)> {
+        let (ours, theirs) = match (Grid::from_board(&self.solution()), Grid::from_board(other)) {
+            (Ok(ours), Ok(theirs)) => (ours, theirs),
+            _ => return vec![],
+        };
+        let mut result = Vec::new();
+        for i in 0..self.height {
+            for j in 0..self.width {
+                let same_lines = ours.edges_around(i, j).iter().all(|e| ours.edge(*e) == theirs.edge(*e));
+                if ours.cell(i, j) != theirs.cell(i, j) || !same_lines {
+                    result.push((i, j));
+                }
+            }
+        }
+        result
+    }
+}
+
+// 2x2のマスの中心を頂点とする正方形を繋げた領域を作り、その外周をループにする
+// 領域に穴がなく、角だけで接する所がなければ、外周は一つのループになる
+pub fn random_loop(width: usize, height: usize, rng: &mut Rng) -> Option<Vec<Vec<DirectionEnum>>> {
+    if width < 2 || height < 2 {
+        return None;
+    }
+    let (sh, sw) = (height - 1, width - 1);
+    let mut region = vec![false; sh * sw];
+    region[rng.below(sh * sw)] = true;
+    let mut failures = 0;
+    while failures < sh * sw * 4 {
+        let mut frontier: Vec<usize> = (0..sh * sw)
+            .filter(|k| !region[*k] && square_neighbours(*k, sh, sw).iter().any(|l| region[*l]))
+            .collect();
+        if frontier.is_empty() {
+            break;
+        }
+        rng.shuffle(&mut frontier);
+        let k = frontier[0];
+        region[k] = true;
+        // ループの内側に線の通らないマスができると、ヒントが増えるので避ける
+        let filled = covers_cell(&region, k, sh, sw) && rng.below(10) < 9;
+        if filled || pinched(&region, k, sh, sw) || has_hole(&region, sh, sw) {
+            region[k] = false;
+            failures += 1;
+            continue;
+        }
+    }
+
+    let inside = |y: i32, x: i32| y >= 0 && x >= 0 && y < sh as i32 && x < sw as i32 && region[y as usize * sw + x as usize];
+    let mut lines = vec![Vec::new(); height * width];
+    for i in 0..height {
+        for j in 0..width {
+            let (y, x) = (i as i32, j as i32);
+            // 右の辺は上下の正方形、下の辺は左右の正方形の片方だけが領域にあれば線になる
+            if j + 1 < width && inside(y - 1, x) != inside(y, x) {
+                lines[i * width + j].push(DirectionEnum::Right);
+                lines[i * width + j + 1].push(DirectionEnum::Left);
+            }
+            if i + 1 < height && inside(y, x - 1) != inside(y, x) {
+                lines[i * width + j].push(DirectionEnum::Down);
+                lines[(i + 1) * width + j].push(DirectionEnum::Up);
+            }
+        }
+    }
+    Some(lines)
+}
+
+fn square_neighbours(k: usize, sh: usize, sw: usize) -> Vec<usize> {
+    let (y, x) = (k / sw, k % sw);
+    let mut result = Vec::new();
+    if y > 0 {
+        result.push(k - sw);
+    }
+    if y + 1 < sh {
+        result.push(k + sw);
+    }
+    if x > 0 {
+        result.push(k - 1);
+    }
+    if x + 1 < sw {
+        result.push(k + 1);
+    }
+    result
+}
+
+// 正方形の角で、斜めの二つだけが領域にあると線が交差する
+fn pinched(region: &[bool], k: usize, sh: usize, sw: usize) -> bool {
+    let inside = |y: i32, x: i32| y >= 0 && x >= 0 && y < sh as i32 && x < sw as i32 && region[y as usize * sw + x as usize];
+    let (y, x) = ((k / sw) as i32, (k % sw) as i32);
+    for (vy, vx) in [(y, x), (y, x + 1), (y + 1, x), (y + 1, x + 1)] {
+        let around = [inside(vy - 1, vx - 1), inside(vy - 1, vx), inside(vy, vx), inside(vy, vx - 1)];
+        if around[0] == around[2] && around[1] == around[3] && around[0] != around[1] {
+            return true;
+        }
+    }
+    false
+}
+
+// 正方形の角のマスが、まわりの四つの正方形すべてに囲まれるか
+fn covers_cell(region: &[bool], k: usize, sh: usize, sw: usize) -> bool {
+    let inside = |y: i32, x: i32| y >= 0 && x >= 0 && y < sh as i32 && x < sw as i32 && region[y as usize * sw + x as usize];
+    let (y, x) = ((k / sw) as i32, (k % sw) as i32);
+    [(y, x), (y, x + 1), (y + 1, x), (y + 1, x + 1)].iter().any(|(vy, vx)| {
+        inside(*vy - 1, *vx - 1) && inside(*vy - 1, *vx) && inside(*vy, *vx) && inside(*vy, *vx - 1)
+    })
+}
+
+fn has_hole(region: &[bool], sh: usize, sw: usize) -> bool {
+    let mut seen = vec![false; sh * sw];
+    let mut stack: Vec<usize> = (0..sh * sw)
+        .filter(|k| !region[*k] && (k / sw == 0 || k / sw == sh - 1 || k % sw == 0 || k % sw == sw - 1))
+        .collect();
+    for k in stack.iter() {
+        seen[*k] = true;
+    }
+    while let Some(k) = stack.pop() {
+        for l in square_neighbours(k, sh, sw) {
+            if !region[l] && !seen[l] {
+                seen[l] = true;
+                stack.push(l);
+            }
+        }
+    }
+    (0..sh * sw).any(|k| !region[k] && !seen[k])
+}
+
+// ループの通らないマスを隣り合わないように黒くし、残りをヒントにする
+pub fn random_answer(width: usize, height: usize, rng: &mut Rng) -> Option<Answer> {
+    let lines = random_loop(width, height, rng)?;
+    let mut answer = Answer { height, width, lines, shaded: vec![false; height * width], clues: vec![None; height * width] };
+    let mut cells: Vec<usize> = (0..height * width).filter(|k| answer.lines[*k].is_empty()).collect();
+    rng.shuffle(&mut cells);
+    for k in cells {
+        let (i, j) = (k / width, k % width);
+        let blocked = DIRECTIONS.iter().any(|dir| answer.ray(i, j, dir).first().map(|(y, x)| answer.shaded[y * width + x]).unwrap_or(false));
+        if blocked {
+            answer.clues[k] = Some(random_direction(&answer, i, j, rng));
+        } else {
+            answer.shaded[k] = true;
+        }
+    }
+    Some(answer)
+}
+
+fn random_direction(answer: &Answer, i: usize, j: usize, rng: &mut Rng) -> DirectionEnum {
+    let dirs: Vec<&DirectionEnum> = DIRECTIONS.iter().filter(|dir| !answer.ray(i, j, dir).is_empty()).collect();
+    if dirs.is_empty() {
+        return DirectionEnum::Up;
+    }
+    dirs[rng.below(dirs.len())].clone()
+}
+
+// ヒントを置く。ループの通るマスに置くときは、ヒントの数字を外して答えを作り直す
+fn place(answer: &Answer, cells: &[(usize, usize)], dir: Option<DirectionEnum>, rng: &mut Rng) -> Option<Answer> {
+    let mut next = answer.clone();
+    let mut rebuild = false;
+    for (n, (i, j)) in cells.iter().enumerate() {
+        let k = i * answer.width + j;
+        if n == 0 && dir.is_some() {
+            next.clues[k] = dir.clone();
+        }
+        if next.clues[k].is_none() {
+            next.clues[k] = Some(random_direction(answer, *i, *j, rng));
+        }
+        next.shaded[k] = false;
+        rebuild |= answer.on_loop(*i, *j);
+    }
+    if !rebuild {
+        return Some(next);
+    }
+    let mut puzzle = next.puzzle();
+    for cell in puzzle.0.iter_mut().flatten() {
+        if let Cell::Wall(WallEnum::Hint(_, num)) = cell {
+            *num = -2;
+        }
+    }
+    sat_solutions(&puzzle, 1).pop().map(|solution| Answer::from_solution(&solution))
+}
+
+// ヒントのマスが対称に並ぶように、足りないマスにもヒントを置く
+fn close(answer: &Answer, symmetry: SymmetryEnum, rng: &mut Rng) -> Option<Answer> {
+    let mut cells = Vec::new();
+    for k in 0..answer.height * answer.width {
+        if answer.clues[k].is_some() {
+            for cell in symmetry.orbit(k / answer.width, k % answer.width, answer.height, answer.width) {
+                if !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+    }
+    place(answer, &cells, None, rng)
+}
+
+// 別解がなくなるまで、黒マスを別解と食い違うマスを指すヒントに置き換えていく
+// ヒントは対称な組ごとに置く
+pub fn make_unique(answer: &mut Answer, symmetry: SymmetryEnum, rng: &mut Rng) -> bool {
+    let limit = answer.height * answer.width * 4;
+    for _ in 0..limit {
+        let solutions = sat_solutions(&answer.puzzle(), 2);
+        if solutions.len() < 2 {
+            return solutions.len() == 1;
+        }
+        let differences: Vec<(usize, usize)> = solutions.iter().map(|other| answer.differences(other)).max_by_key(|d| d.len()).unwrap_or_default();
+        let mut candidates: Vec<(usize, DirectionEnum)> = Vec::new();
+        for k in 0..answer.height * answer.width {
+            let (i, j) = (k / answer.width, k % answer.width);
+            if !answer.shaded[k] {
+                continue;
+            }
+            for dir in DIRECTIONS.iter() {
+                if answer.ray(i, j, dir).iter().any(|cell| differences.contains(cell)) || differences.contains(&(i, j)) {
+                    candidates.push((k, dir.clone()));
+                }
+            }
+        }
+        rng.shuffle(&mut candidates);
+        let mut next = None;
+        for (k, dir) in candidates {
+            let orbit = symmetry.orbit(k / answer.width, k % answer.width, answer.height, answer.width);
+            next = place(answer, &orbit, Some(dir), rng);
+            if next.is_some() {
+                break;
+            }
+        }
+        if next.is_none() {
+            // 線の引き方だけが違うときは、ループの通るマスをヒントにして答えを作り直す
+            let mut cells: Vec<(usize, usize)> = differences.iter().filter(|(i, j)| answer.on_loop(*i, *j)).cloned().collect();
+            rng.shuffle(&mut cells);
+            for (i, j) in cells {
+                next = place(answer, &symmetry.orbit(i, j, answer.height, answer.width), None, rng);
+                if next.is_some() {
+                    break;
+                }
+            }
+        }
+        match next {
+            Some(next) => *answer = next,
+            None => return false,
+        }
+    }
+    false
+}
+
+pub fn generate(width: usize, height: usize, seed: u64) -> Option<Generated> {
+    generate_symmetric(width, height, seed, SymmetryEnum::None)
+}
+
+pub fn generate_symmetric(width: usize, height: usize, seed: u64, symmetry: SymmetryEnum) -> Option<Generated> {
+    if !symmetry.fits(height, width) {
+        return None;
+    }
+    let mut rng = Rng::new(seed);
+    for _ in 0..MAX_ATTEMPTS {
+        let answer = random_answer(width, height, &mut rng)?;
+        let mut answer = match close(&answer, symmetry, &mut rng) {
+            Some(answer) => answer,
+            None => continue,
+        };
+        if make_unique(&mut answer, symmetry, &mut rng) {
+            let board = answer.puzzle();
+            let pzpr = encode_board(&board);
+            return Some(Generated { board, solution: answer.solution(), pzpr });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_board;
+    use crate::diff::diff;
+
+    #[test]
+    fn generated_puzzles_are_unique_and_reproducible() {
+        for seed in 0..6 {
+            let generated = generate(6, 6, seed).unwrap();
+            assert_eq!(generate(6, 6, seed).unwrap().pzpr, generated.pzpr);
+            let solutions = sat_solutions(&generated.board, 2);
+            assert_eq!(solutions.len(), 1);
+            assert!(diff(&solutions[0], &generated.solution).unwrap().is_empty());
+            // pzprで書き出して読み直しても同じ問題
+            let board = create_board(&generated.pzpr);
+            assert_eq!(encode_board(&board), generated.pzpr);
+            assert!(diff(&board, &generated.board).unwrap().is_empty());
+        }
+    }
+}
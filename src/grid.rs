@@ -0,0 +1,505 @@
+use std::fmt;
+use union_find::{QuickUnionUf, UnionBySize, UnionFind};
+use crate::{Board, Cell, CheckResultInvalidEnum, DirectionEnum, WallEnum};
+
+// マスの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CellStateEnum {
+    Unknown,
+    Shaded,   // 黒マス
+    Unshaded, // 線が通るマス
+    Clue,     // ヒントのマス
+}
+
+// 辺の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeStateEnum {
+    Unknown,
+    Line,
+    Cross, // 線が引かれないことが確定
+}
+
+// Right(i, j) は (i, j) と (i, j + 1) の間、Down(i, j) は (i, j) と (i + 1, j) の間の辺
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EdgeEnum {
+    Right(usize, usize),
+    Down(usize, usize),
+}
+
+impl EdgeEnum {
+    pub fn cells(&self) -> ((usize, usize), (usize, usize)) {
+        match *self {
+            EdgeEnum::Right(i, j) => ((i, j), (i, j + 1)),
+            EdgeEnum::Down(i, j) => ((i, j), (i + 1, j)),
+        }
+    }
+}
+
+impl fmt::Display for EdgeEnum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (a, b) = self.cells();
+        write!(f, "({},{})-({},{})", a.0, a.1, b.0, b.1)
+    }
+}
+
+// 盤面について確定する事実
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FactEnum {
+    Shaded(usize, usize),
+    Unshaded(usize, usize),
+    Line(EdgeEnum),
+    Cross(EdgeEnum),
+}
+
+impl FactEnum {
+    pub fn negate(&self) -> FactEnum {
+        match *self {
+            FactEnum::Shaded(i, j) => FactEnum::Unshaded(i, j),
+            FactEnum::Unshaded(i, j) => FactEnum::Shaded(i, j),
+            FactEnum::Line(e) => FactEnum::Cross(e),
+            FactEnum::Cross(e) => FactEnum::Line(e),
+        }
+    }
+
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        match *self {
+            FactEnum::Shaded(i, j) | FactEnum::Unshaded(i, j) => vec![(i, j)],
+            FactEnum::Line(e) | FactEnum::Cross(e) => {
+                let (a, b) = e.cells();
+                vec![a, b]
+            }
+        }
+    }
+}
+
+impl fmt::Display for FactEnum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FactEnum::Shaded(i, j) => write!(f, "({},{}) is shaded", i, j),
+            FactEnum::Unshaded(i, j) => write!(f, "({},{}) is on the loop", i, j),
+            FactEnum::Line(e) => write!(f, "line between {}", e),
+            FactEnum::Cross(e) => write!(f, "no line between {}", e),
+        }
+    }
+}
+
+pub const DIRECTIONS: [DirectionEnum; 4] = [DirectionEnum::Up, DirectionEnum::Down, DirectionEnum::Left, DirectionEnum::Right];
+
+// マスと辺の状態を持つ盤面
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid {
+    height: usize,
+    width: usize,
+    cells: Vec<CellStateEnum>,
+    clues: Vec<Option<(DirectionEnum, i64)>>,
+    right: Vec<EdgeStateEnum>,
+    down: Vec<EdgeStateEnum>,
+}
+
+impl Grid {
+    pub fn from_board(board: &Board) -> Result<Grid, CheckResultInvalidEnum> {
+        let height = board.0.len();
+        let width = if height == 0 { 0 } else { board.0[0].len() };
+        let mut grid = Grid {
+            height,
+            width,
+            cells: vec![CellStateEnum::Unknown; height * width],
+            clues: vec![None; height * width],
+            right: vec![EdgeStateEnum::Unknown; height * width.saturating_sub(1)],
+            down: vec![EdgeStateEnum::Unknown; height.saturating_sub(1) * width],
+        };
+        for (i, row) in board.0.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                match cell {
+                    Cell::Wall(WallEnum::Hint(dir, num)) => {
+                        grid.cells[i * width + j] = CellStateEnum::Clue;
+                        grid.clues[i * width + j] = Some((dir.clone(), *num));
+                    }
+                    Cell::Wall(WallEnum::Wall) => grid.cells[i * width + j] = CellStateEnum::Shaded,
+                    Cell::Space(_, _) => grid.cells[i * width + j] = CellStateEnum::Unshaded,
+                    Cell::Unknown => {}
+                }
+            }
+        }
+        // ヒントのマスには線が入らない
+        for i in 0..height {
+            for j in 0..width {
+                if grid.cell(i, j) == CellStateEnum::Clue {
+                    for e in grid.edges_around(i, j) {
+                        grid.apply(FactEnum::Cross(e))?;
+                    }
+                }
+            }
+        }
+        for (i, row) in board.0.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                match cell {
+                    Cell::Wall(WallEnum::Wall) => grid.apply(FactEnum::Shaded(i, j)).map(|_| ())?,
+                    Cell::Space(one, two) => {
+                        for dir in [one, two].into_iter().flatten() {
+                            if *dir == DirectionEnum::None {
+                                continue;
+                            }
+                            match grid.edge_towards(i, j, dir) {
+                                Some(e) => grid.apply(FactEnum::Line(e)).map(|_| ())?,
+                                None => return Err(CheckResultInvalidEnum::Line(i as i32, j as i32)),
+                            }
+                        }
+                        // 2方向とも決まっていれば残りの辺は引かれない
+                        if let (Some(_), Some(_)) = (one, two) {
+                            for e in grid.edges_around(i, j) {
+                                if grid.edge(e) == EdgeStateEnum::Unknown {
+                                    grid.apply(FactEnum::Cross(e))?;
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn to_board(&self) -> Board {
+        let mut rows = Vec::new();
+        for i in 0..self.height {
+            let mut row = Vec::new();
+            for j in 0..self.width {
+                let lines: Vec<DirectionEnum> = DIRECTIONS
+                    .iter()
+                    .filter(|dir| self.edge_towards(i, j, dir).map(|e| self.edge(e)) == Some(EdgeStateEnum::Line))
+                    .cloned()
+                    .collect();
+                row.push(match self.cell(i, j) {
+                    CellStateEnum::Clue => {
+                        let (dir, num) = self.clue(i, j).unwrap();
+                        Cell::Wall(WallEnum::Hint(dir.clone(), *num))
+                    }
+                    CellStateEnum::Shaded => Cell::Wall(WallEnum::Wall),
+                    CellStateEnum::Unknown if lines.is_empty() => Cell::Unknown,
+                    _ => Cell::Space(lines.first().cloned(), lines.get(1).cloned()),
+                });
+            }
+            rows.push(row);
+        }
+        Board(rows)
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn cell(&self, i: usize, j: usize) -> CellStateEnum {
+        self.cells[i * self.width + j]
+    }
+
+    pub fn clue(&self, i: usize, j: usize) -> Option<&(DirectionEnum, i64)> {
+        self.clues[i * self.width + j].as_ref()
+    }
+
+    pub fn edge(&self, e: EdgeEnum) -> EdgeStateEnum {
+        match e {
+            EdgeEnum::Right(i, j) => self.right[i * (self.width - 1) + j],
+            EdgeEnum::Down(i, j) => self.down[i * self.width + j],
+        }
+    }
+
+    pub fn edge_towards(&self, i: usize, j: usize, dir: &DirectionEnum) -> Option<EdgeEnum> {
+        match dir {
+            DirectionEnum::Up if i > 0 => Some(EdgeEnum::Down(i - 1, j)),
+            DirectionEnum::Down if i + 1 < self.height => Some(EdgeEnum::Down(i, j)),
+            DirectionEnum::Left if j > 0 => Some(EdgeEnum::Right(i, j - 1)),
+            DirectionEnum::Right if j + 1 < self.width => Some(EdgeEnum::Right(i, j)),
+            _ => None,
+        }
+    }
+
+    pub fn neighbour(&self, i: usize, j: usize, dir: &DirectionEnum) -> Option<(usize, usize)> {
+        let vec = dir.to_vector();
+        let next = (i as i32 + vec.0, j as i32 + vec.1);
+        if next.0 < 0 || next.0 >= self.height as i32 || next.1 < 0 || next.1 >= self.width as i32 || next == (i as i32, j as i32) {
+            return None;
+        }
+        Some((next.0 as usize, next.1 as usize))
+    }
+
+    pub fn neighbours(&self, i: usize, j: usize) -> Vec<(usize, usize)> {
+        DIRECTIONS.iter().filter_map(|dir| self.neighbour(i, j, dir)).collect()
+    }
+
+    pub fn edges_around(&self, i: usize, j: usize) -> Vec<EdgeEnum> {
+        DIRECTIONS.iter().filter_map(|dir| self.edge_towards(i, j, dir)).collect()
+    }
+
+    pub fn all_edges(&self) -> Vec<EdgeEnum> {
+        let mut edges = Vec::new();
+        for i in 0..self.height {
+            for j in 0..self.width {
+                if j + 1 < self.width {
+                    edges.push(EdgeEnum::Right(i, j));
+                }
+                if i + 1 < self.height {
+                    edges.push(EdgeEnum::Down(i, j));
+                }
+            }
+        }
+        edges
+    }
+
+    // ヒントの矢印方向にあるマス
+    pub fn ray(&self, i: usize, j: usize, dir: &DirectionEnum) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        let mut now = (i, j);
+        while let Some(next) = self.neighbour(now.0, now.1, dir) {
+            result.push(next);
+            now = next;
+        }
+        result
+    }
+
+    pub fn line_count(&self, i: usize, j: usize) -> usize {
+        self.edges_around(i, j).into_iter().filter(|e| self.edge(*e) == EdgeStateEnum::Line).count()
+    }
+
+    // まだ線を引く可能性のある辺の数
+    pub fn open_count(&self, i: usize, j: usize) -> usize {
+        self.edges_around(i, j).into_iter().filter(|e| self.edge(*e) != EdgeStateEnum::Cross).count()
+    }
+
+    // 黒マスにできるか
+    pub fn can_shade(&self, i: usize, j: usize) -> bool {
+        self.cell(i, j) == CellStateEnum::Unknown
+            && self.line_count(i, j) == 0
+            && self.neighbours(i, j).into_iter().all(|(y, x)| self.cell(y, x) != CellStateEnum::Shaded)
+    }
+
+    // (黒マスの数, まだ置ける黒マスの最大数)
+    pub fn clue_counts(&self, i: usize, j: usize, dir: &DirectionEnum) -> (i64, i64) {
+        let mut shaded = 0;
+        let mut max = 0;
+        let mut run = 0;
+        for (y, x) in self.ray(i, j, dir) {
+            if self.cell(y, x) == CellStateEnum::Shaded {
+                shaded += 1;
+            }
+            if self.can_shade(y, x) {
+                run += 1;
+            } else {
+                max += (run + 1) / 2;
+                run = 0;
+            }
+        }
+        max += (run + 1) / 2;
+        (shaded, max)
+    }
+
+    pub fn holds(&self, fact: FactEnum) -> Option<bool> {
+        let (positive, state) = match fact {
+            FactEnum::Shaded(i, j) => (CellStateEnum::Shaded, self.cell(i, j)),
+            FactEnum::Unshaded(i, j) => (CellStateEnum::Unshaded, self.cell(i, j)),
+            FactEnum::Line(e) | FactEnum::Cross(e) => {
+                let positive = if let FactEnum::Line(_) = fact { EdgeStateEnum::Line } else { EdgeStateEnum::Cross };
+                return match self.edge(e) {
+                    EdgeStateEnum::Unknown => None,
+                    state => Some(state == positive),
+                };
+            }
+        };
+        match state {
+            CellStateEnum::Unknown => None,
+            state => Some(state == positive),
+        }
+    }
+
+    // 事実を書き込む。状態が変わったらtrue
+    pub fn apply(&mut self, fact: FactEnum) -> Result<bool, CheckResultInvalidEnum> {
+        match fact {
+            FactEnum::Shaded(i, j) | FactEnum::Unshaded(i, j) => {
+                let state = if let FactEnum::Shaded(_, _) = fact { CellStateEnum::Shaded } else { CellStateEnum::Unshaded };
+                let now = self.cell(i, j);
+                if now == state {
+                    return Ok(false);
+                }
+                if now != CellStateEnum::Unknown {
+                    return Err(CheckResultInvalidEnum::Conflict(i as i32, j as i32));
+                }
+                if state == CellStateEnum::Shaded && self.line_count(i, j) > 0 {
+                    return Err(CheckResultInvalidEnum::Line(i as i32, j as i32));
+                }
+                self.cells[i * self.width + j] = state;
+                Ok(true)
+            }
+            FactEnum::Line(e) | FactEnum::Cross(e) => {
+                let state = if let FactEnum::Line(_) = fact { EdgeStateEnum::Line } else { EdgeStateEnum::Cross };
+                let now = self.edge(e);
+                let (a, b) = e.cells();
+                if now == state {
+                    return Ok(false);
+                }
+                if now != EdgeStateEnum::Unknown {
+                    return Err(CheckResultInvalidEnum::Conflict(a.0 as i32, a.1 as i32));
+                }
+                if state == EdgeStateEnum::Line {
+                    for (y, x) in [a, b] {
+                        if let CellStateEnum::Shaded | CellStateEnum::Clue = self.cell(y, x) {
+                            return Err(CheckResultInvalidEnum::Line(y as i32, x as i32));
+                        }
+                    }
+                }
+                match e {
+                    EdgeEnum::Right(i, j) => self.right[i * (self.width - 1) + j] = state,
+                    EdgeEnum::Down(i, j) => self.down[i * self.width + j] = state,
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    // 未確定の事実の候補
+    pub fn undecided(&self) -> Vec<FactEnum> {
+        let mut result = Vec::new();
+        for i in 0..self.height {
+            for j in 0..self.width {
+                if self.cell(i, j) == CellStateEnum::Unknown {
+                    result.push(FactEnum::Shaded(i, j));
+                }
+            }
+        }
+        for e in self.all_edges() {
+            if self.edge(e) == EdgeStateEnum::Unknown {
+                result.push(FactEnum::Line(e));
+            }
+        }
+        result
+    }
+
+    // 確定している事実
+    pub fn facts(&self) -> Vec<FactEnum> {
+        let mut result = Vec::new();
+        for i in 0..self.height {
+            for j in 0..self.width {
+                match self.cell(i, j) {
+                    CellStateEnum::Shaded => result.push(FactEnum::Shaded(i, j)),
+                    CellStateEnum::Unshaded => result.push(FactEnum::Unshaded(i, j)),
+                    _ => {}
+                }
+            }
+        }
+        for e in self.all_edges() {
+            match self.edge(e) {
+                EdgeStateEnum::Line => result.push(FactEnum::Line(e)),
+                EdgeStateEnum::Cross => result.push(FactEnum::Cross(e)),
+                EdgeStateEnum::Unknown => {}
+            }
+        }
+        result
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.cells.iter().all(|c| *c != CellStateEnum::Unknown)
+            && self.right.iter().chain(self.down.iter()).all(|e| *e != EdgeStateEnum::Unknown)
+    }
+
+    // 線で繋がったマスの集合
+    pub fn line_components(&self) -> QuickUnionUf<UnionBySize> {
+        let mut uf = QuickUnionUf::<UnionBySize>::new(self.height * self.width);
+        for e in self.all_edges() {
+            if self.edge(e) == EdgeStateEnum::Line {
+                let (a, b) = e.cells();
+                uf.union(a.0 * self.width + a.1, b.0 * self.width + b.1);
+            }
+        }
+        uf
+    }
+
+    // 市松模様の色ごとの (線が通るマス, 未確定のマス) の数
+    pub fn parity_counts(&self) -> [(i64, i64); 2] {
+        let mut counts = [(0, 0); 2];
+        for i in 0..self.height {
+            for j in 0..self.width {
+                match self.cell(i, j) {
+                    CellStateEnum::Unshaded => counts[(i + j) % 2].0 += 1,
+                    CellStateEnum::Unknown => counts[(i + j) % 2].1 += 1,
+                    _ => {}
+                }
+            }
+        }
+        counts
+    }
+
+    // 盤面全体の矛盾を調べる
+    pub fn validate(&self) -> Result<(), CheckResultInvalidEnum> {
+        for i in 0..self.height {
+            for j in 0..self.width {
+                let (ii, jj) = (i as i32, j as i32);
+                match self.cell(i, j) {
+                    CellStateEnum::Shaded => {
+                        if self.neighbours(i, j).into_iter().any(|(y, x)| self.cell(y, x) == CellStateEnum::Shaded) {
+                            return Err(CheckResultInvalidEnum::AdjacentWall(ii, jj));
+                        }
+                    }
+                    CellStateEnum::Clue => {
+                        if let Some((dir, num)) = self.clue(i, j) {
+                            if *dir == DirectionEnum::None || *num < 0 {
+                                continue;
+                            }
+                            let (shaded, max) = self.clue_counts(i, j, dir);
+                            if shaded > *num || shaded + max < *num {
+                                return Err(CheckResultInvalidEnum::Hint(ii, jj));
+                            }
+                        }
+                    }
+                    CellStateEnum::Unshaded => {
+                        if self.line_count(i, j) > 2 || self.open_count(i, j) < 2 {
+                            return Err(CheckResultInvalidEnum::Line(ii, jj));
+                        }
+                    }
+                    CellStateEnum::Unknown => {
+                        if self.line_count(i, j) > 2 || (self.line_count(i, j) > 0 && self.open_count(i, j) < 2) {
+                            return Err(CheckResultInvalidEnum::Line(ii, jj));
+                        }
+                    }
+                }
+            }
+        }
+
+        // 閉じたループがあれば、それ以外に線があってはいけない
+        let mut uf = self.line_components();
+        let mut closed = vec![true; self.height * self.width];
+        let mut used = vec![false; self.height * self.width];
+        for i in 0..self.height {
+            for j in 0..self.width {
+                let count = self.line_count(i, j);
+                if count > 0 || self.cell(i, j) == CellStateEnum::Unshaded {
+                    let root = uf.find(i * self.width + j);
+                    used[root] = true;
+                    if count != 2 {
+                        closed[root] = false;
+                    }
+                }
+            }
+        }
+        let roots: Vec<usize> = (0..self.height * self.width).filter(|r| used[*r]).collect();
+        if roots.len() > 1 {
+            if let Some(r) = roots.iter().find(|r| closed[**r]) {
+                return Err(CheckResultInvalidEnum::MultipleLoops((*r / self.width) as i32, (*r % self.width) as i32));
+            }
+        }
+
+        // 閉じたループの長さは偶数なので、市松模様の2色の数は等しい
+        let [(black, black_unknown), (white, white_unknown)] = self.parity_counts();
+        if white - black > black_unknown || black - white > white_unknown {
+            return Err(CheckResultInvalidEnum::NoAnswer);
+        }
+
+        if self.is_complete() && roots.len() != 1 {
+            return Err(CheckResultInvalidEnum::NoAnswer);
+        }
+        Ok(())
+    }
+}
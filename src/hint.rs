@@ -0,0 +1,218 @@
+use crate::{clear_board, dir_to_char, Board};
+use crate::grid::{CellStateEnum, FactEnum, Grid};
+use crate::technique::{branch, next_step, search, Step};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HintEnum {
+    Deduction(Step),           // 次に分かること
+    Reveal(FactEnum),          // 推論では進めないので答えを一つ見せる
+    Mistake(Vec<MistakeEnum>), // 解と食い違う印
+    Solved,
+    NoAnswer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MistakeEnum {
+    Fact(FactEnum),      // 解と食い違う事実
+    Cell(usize, usize),  // 盤面の外に向かう線など、それだけで成り立たない印のマス
+}
+
+// 利用者が書き込んだ印。バツは盤面に残らないので含めない
+fn marks(grid: &Grid) -> Vec<FactEnum> {
+    grid.facts()
+        .into_iter()
+        .filter(|fact| !matches!(fact, FactEnum::Cross(_)))
+        .collect()
+}
+
+fn mistakes(marks: &[FactEnum], solution: &Grid) -> Vec<FactEnum> {
+    marks.iter().filter(|fact| solution.holds(**fact) != Some(true)).cloned().collect()
+}
+
+fn closest_mistakes(marks: &[FactEnum], answers: &[Grid]) -> Vec<MistakeEnum> {
+    let wrong = answers.iter().map(|answer| mistakes(marks, answer)).min_by_key(|w| w.len()).unwrap_or_default();
+    wrong.into_iter().map(MistakeEnum::Fact).collect()
+}
+
+// 途中まで埋めた盤面から、次の一番簡単な一手を返す
+pub fn hint(board: &Board) -> HintEnum {
+    let mut answers = Vec::new();
+    if let Ok(puzzle) = Grid::from_board(&clear_board(board)) {
+        search(&puzzle, 2, &mut answers);
+    }
+    if answers.is_empty() {
+        return HintEnum::NoAnswer;
+    }
+
+    let grid = match Grid::from_board(board) {
+        Ok(grid) => grid,
+        Err(_) => {
+            // 盤面として成り立たない印があるので、マスごとに読んで解と比べる
+            let mut single_marks = Vec::new();
+            let mut cells = Vec::new();
+            for (i, row) in board.0.iter().enumerate() {
+                for j in 0..row.len() {
+                    let mut single = clear_board(board);
+                    single.0[i][j] = board.0[i][j].clone();
+                    match Grid::from_board(&single) {
+                        Ok(g) => single_marks.extend(marks(&g)),
+                        Err(_) => cells.push(MistakeEnum::Cell(i, j)),
+                    }
+                }
+            }
+            single_marks.sort_by_key(|fact| fact.cells());
+            single_marks.dedup();
+            // 複数解なら一番食い違いの少ない解と比べる
+            let mut wrong = closest_mistakes(&single_marks, &answers);
+            wrong.extend(cells);
+            return HintEnum::Mistake(wrong);
+        }
+    };
+    let marks = marks(&grid);
+
+    // 唯一解なら解と比べる。複数解なら印と矛盾しない解を探す
+    let mut consistent: Vec<Grid> = answers.iter().filter(|answer| mistakes(&marks, answer).is_empty()).cloned().collect();
+    if consistent.is_empty() && answers.len() > 1 {
+        search(&grid, 1, &mut consistent);
+    }
+    let answer = match consistent.first() {
+        Some(answer) => answer,
+        None => return HintEnum::Mistake(closest_mistakes(&marks, &answers)),
+    };
+
+    if grid.is_complete() {
+        return HintEnum::Solved;
+    }
+    match next_step(&grid) {
+        Ok(Some(step)) => HintEnum::Deduction(step),
+        _ => {
+            let fact = branch(&grid).unwrap();
+            HintEnum::Reveal(if answer.holds(fact) == Some(true) { fact } else { fact.negate() })
+        }
+    }
+}
+
+fn describe_cell(grid: &Grid, i: usize, j: usize) -> String {
+    match grid.cell(i, j) {
+        CellStateEnum::Clue => match grid.clue(i, j) {
+            Some((dir, num)) if *num >= 0 => format!("the clue {}{} at ({},{})", dir_to_char(dir.clone()), num, i, j),
+            Some((dir, _)) => format!("the clue {}? at ({},{})", dir_to_char(dir.clone()), i, j),
+            None => format!("the clue at ({},{})", i, j),
+        },
+        CellStateEnum::Shaded => format!("the shaded cell at ({},{})", i, j),
+        CellStateEnum::Unshaded => format!("the loop cell at ({},{})", i, j),
+        CellStateEnum::Unknown => format!("the cell at ({},{})", i, j),
+    }
+}
+
+// ヒントを文章にする
+pub fn describe(board: &Board, hint: &HintEnum) -> String {
+    let grid = Grid::from_board(board).or_else(|_| Grid::from_board(&clear_board(board)));
+    let facts = |facts: &[FactEnum]| facts.iter().map(|fact| fact.to_string()).collect::<Vec<String>>().join(", ");
+    match hint {
+        HintEnum::Deduction(step) => {
+            let mut text = format!("{}. {}: {}.", facts(&step.changes), step.technique, step.technique.description());
+            if let Ok(grid) = grid {
+                let reasons: Vec<String> = step.reasons.iter().map(|(i, j)| describe_cell(&grid, *i, *j)).collect();
+                if !reasons.is_empty() {
+                    text += &format!(" Look at {}.", reasons.join(", "));
+                }
+            }
+            text
+        }
+        HintEnum::Reveal(fact) => format!("No deduction is left without guessing. In the solution, {}.", fact),
+        HintEnum::Mistake(wrong) if wrong.is_empty() => "Your marks already contradict the solution.".to_string(),
+        HintEnum::Mistake(wrong) => {
+            let wrong_facts: Vec<FactEnum> = wrong.iter().filter_map(|m| if let MistakeEnum::Fact(fact) = m { Some(*fact) } else { None }).collect();
+            let cells: Vec<String> = wrong.iter().filter_map(|m| if let MistakeEnum::Cell(i, j) = m { Some(format!("({},{})", i, j)) } else { None }).collect();
+            let mut text = Vec::new();
+            if !wrong_facts.is_empty() {
+                text.push(format!("Your marks contradict the solution: {}.", facts(&wrong_facts)));
+            }
+            if !cells.is_empty() {
+                text.push(format!("The marks at {} cannot be drawn on this board.", cells.join(", ")));
+            }
+            text.join(" ")
+        }
+        HintEnum::Solved => "The board is already solved.".to_string(),
+        HintEnum::NoAnswer => "The puzzle has no solution.".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::sat_solutions;
+    use crate::format::parse_text;
+    use crate::grid::EdgeEnum;
+    use crate::technique::solutions;
+    use crate::{create_board, Cell, DirectionEnum, WallEnum};
+
+    #[test]
+    fn deduction() {
+        let board = create_board("5/5/g22q");
+        match hint(&board) {
+            HintEnum::Deduction(step) => {
+                let solution = Grid::from_board(&sat_solutions(&board, 1)[0]).unwrap();
+                assert!(step.changes.iter().all(|fact| solution.holds(*fact) == Some(true)));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn reveal() {
+        // 3x3で十字に線のマスを置くと、推論では四隅が決まらない
+        let board = parse_text("??..??\n..??..\n??..??\n").unwrap();
+        match hint(&board) {
+            HintEnum::Reveal(fact) => {
+                let solutions: Vec<Grid> = solutions(&board, 10).iter().map(|b| Grid::from_board(b).unwrap()).collect();
+                assert!(solutions.iter().any(|solution| solution.holds(fact) == Some(true)));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn mistake() {
+        let mut board = create_board("5/5/g22q");
+        // 解では(0,0)はループ上にある
+        board.0[0][0] = Cell::Wall(WallEnum::Wall);
+        let result = hint(&board);
+        assert_eq!(result, HintEnum::Mistake(vec![MistakeEnum::Fact(FactEnum::Shaded(0, 0))]));
+        assert_eq!(describe(&board, &result), "Your marks contradict the solution: (0,0) is shaded.");
+    }
+
+    #[test]
+    fn mistake_with_invalid_mark() {
+        let mut board = create_board("5/5/g22q");
+        // 盤面の外に向かう線は、それだけで成り立たない
+        board.0[0][0] = Cell::Space(Some(DirectionEnum::Up), None);
+        let result = hint(&board);
+        assert_eq!(result, HintEnum::Mistake(vec![MistakeEnum::Cell(0, 0)]));
+        assert_eq!(describe(&board, &result), "The marks at (0,0) cannot be drawn on this board.");
+
+        // 正しい印は報告せず、食い違う印と成り立たない印だけを返す。(2,0)の線は下だけが正しい
+        board.0[4][2] = Cell::Space(None, None);
+        board.0[2][0] = Cell::Space(Some(DirectionEnum::Up), Some(DirectionEnum::Down));
+        assert_eq!(hint(&board), HintEnum::Mistake(vec![
+            MistakeEnum::Fact(FactEnum::Line(EdgeEnum::Down(1, 0))),
+            MistakeEnum::Fact(FactEnum::Unshaded(4, 2)),
+            MistakeEnum::Cell(0, 0),
+        ]));
+    }
+
+    #[test]
+    fn solved() {
+        let board = create_board("5/5/g22q");
+        let solution = sat_solutions(&board, 1).pop().unwrap();
+        assert_eq!(hint(&solution), HintEnum::Solved);
+    }
+
+    #[test]
+    fn no_answer() {
+        let board = create_board("1/1/");
+        assert_eq!(hint(&board), HintEnum::NoAnswer);
+        assert_eq!(describe(&board, &HintEnum::NoAnswer), "The puzzle has no solution.");
+    }
+}
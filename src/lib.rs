@@ -0,0 +1,1166 @@
+use std::cmp::PartialEq;
+use std::fmt;
+use priority_queue::PriorityQueue;
+use union_find::{QuickUnionUf, UnionBySize, UnionFind};
+
+pub mod art;
+pub mod backbone;
+pub mod cnf;
+pub mod daily;
+pub mod design;
+pub mod diff;
+pub mod format;
+pub mod generator;
+pub mod grid;
+pub mod hint;
+pub mod minimize;
+pub mod pdf;
+pub mod png;
+pub mod random;
+pub mod rating;
+pub mod render;
+pub mod report;
+pub mod sat;
+pub mod session;
+pub mod svg;
+pub mod symmetry;
+pub mod target;
+pub mod technique;
+pub mod terminal;
+pub mod tikz;
+pub mod trace;
+pub mod unsat;
+
+#[derive(Clone)]
+pub struct Board(pub Vec<Vec<Cell>>);
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.0.iter() {
+            for cell in row.iter() {
+                match cell {
+                    Cell::Wall(wall) => {
+                        match wall {
+                            WallEnum::Wall => write!(f, " W")?,
+                            WallEnum::Hint(dir, num) => write!(f, "{}{}", dir_to_char(dir.clone()), num)?,
+                        }
+                    }
+                    Cell::Space(Some(one), Some(twh)) => {
+                        write!(f, "{}{}", dir_to_char(one.clone()), dir_to_char(twh.clone()))?
+                    }
+                    Cell::Space(Some(one), _) => {
+                        write!(f, "{}?", dir_to_char(one.clone()))?
+                    }
+                    Cell::Space(_, _) => write!(f, "..")?,
+                    Cell::Unknown => write!(f, "??")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        write!(f, " ")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DirectionEnum {
+    None,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl DirectionEnum {
+    fn to_vector(&self) -> (i32, i32) {
+        match self {
+            DirectionEnum::None => (0, 0),
+            DirectionEnum::Up => (-1, 0),
+            DirectionEnum::Down => (1, 0),
+            DirectionEnum::Left => (0, -1),
+            DirectionEnum::Right => (0, 1),
+        }
+    }
+    fn reverse(&self) -> DirectionEnum {
+        match self {
+            DirectionEnum::None => DirectionEnum::None,
+            DirectionEnum::Up => DirectionEnum::Down,
+            DirectionEnum::Down => DirectionEnum::Up,
+            DirectionEnum::Left => DirectionEnum::Right,
+            DirectionEnum::Right => DirectionEnum::Left,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WallEnum {
+    Wall,
+    Hint(DirectionEnum, i64),
+}
+
+#[derive(Debug, Clone)]
+pub enum CellEnum {
+    Wall(WallEnum),
+    Space(Option<DirectionEnum>, Option<DirectionEnum>),
+    Unknown,
+}
+
+pub type Cell = CellEnum;
+
+pub fn create_board(problem: &str) -> Board {
+    // まずは/で分割
+    let iter: Vec<&str> = problem.split("/").collect();
+
+    // 幅と高さを取得
+    let width: usize = iter[0].parse().unwrap();
+    let height: usize = iter[1].parse().unwrap();
+
+    // iter[2]を一文字ずつ取り出して処理
+    let mut b = create_board_sub(iter[2].chars());
+    b.extend(vec![CellEnum::Unknown; height * width - b.len()]);
+
+    Board(b.chunks(width).map(|x| x.to_vec()).collect())
+}
+
+// ヒント以外を消した盤面
+pub fn clear_board(board: &Board) -> Board {
+    Board(board.0.iter().map(|row| row.iter().map(|cell| match cell {
+        Cell::Wall(WallEnum::Hint(_, _)) => cell.clone(),
+        _ => Cell::Unknown,
+    }).collect()).collect())
+}
+
+
+/*
+var ca = bstr.charAt(i), cell=bd.cell[c];
+
+if(this.include(ca,"0","4")){
+	var ca1 = bstr.charAt(i+1);
+	cell.qdir = parseInt(ca,16);
+	cell.qnum = (ca1!=="." ? parseInt(ca1,16) : -2);
+	i++;
+}
+else if(this.include(ca,"5","9")){
+	cell.qdir = parseInt(ca,16)-5;
+	cell.qnum = parseInt(bstr.substr(i+1,2),16);
+	i+=2;
+}
+else if(ca==="-"){
+	cell.qdir = parseInt(bstr.substr(i+1,1),16);
+	cell.qnum = parseInt(bstr.substr(i+2,3),16);
+	i+=4;
+}
+else if(ca>='a' && ca<='z'){ c+=(parseInt(ca,36)-10);}
+
+c++;
+if(!bd.cell[c]){ break;}
+ */
+fn create_board_sub(mut chars: std::str::Chars) -> Vec<CellEnum> {
+    let now = chars.next();
+    match now {
+        None => {
+            Vec::new()
+        }
+        Some(c) => {
+            match c {
+                '0'..='4' => {
+                    let next = chars.next();
+                    let qdir = c.to_digit(16).unwrap();
+                    let qnum = match next {
+                        Some('.') => -2,
+                        Some(ca1) => ca1.to_digit(16).unwrap() as i64,
+                        None => -2,
+                    };
+                    let mut result = vec![Cell::Wall(WallEnum::Hint(dir_to_direction_enum(qdir), qnum))];
+                    result.extend(create_board_sub(chars));
+                    result
+                }
+                '5'..='9' => {
+                    let qdir = c.to_digit(16).unwrap() - 5;
+                    let qnum = chars.next().unwrap().to_digit(16).unwrap() as i64 * 16 + chars.next().unwrap().to_digit(16).unwrap() as i64;
+                    let mut result = vec![Cell::Wall(WallEnum::Hint(dir_to_direction_enum(qdir), qnum))];
+                    result.extend(create_board_sub(chars));
+                    result
+                }
+                '-' => {
+                    let qdir = chars.next().unwrap().to_digit(16).unwrap();
+                    let qnum = chars.next().unwrap().to_digit(16).unwrap() as i64 * 16 * 16 + chars.next().unwrap().to_digit(16).unwrap() as i64 * 16 + chars.next().unwrap().to_digit(16).unwrap() as i64;
+                    let mut result = vec![Cell::Wall(WallEnum::Hint(dir_to_direction_enum(qdir), qnum))];
+                    result.extend(create_board_sub(chars));
+                    result
+                }
+                'a'..='z' => {
+                    let c = c.to_digit(36).unwrap() - 'a'.to_digit(36).unwrap() + 1;
+                    let mut result = vec![Cell::Unknown; c as usize];
+                    result.extend(create_board_sub(chars));
+                    result
+                }
+                _ => {
+                    panic!("error")
+                }
+            }
+        }
+    }
+}
+
+// create_boardの逆。ヒント以外のマスは空白として書く
+pub fn encode_board(board: &Board) -> String {
+    let height = board.0.len();
+    let width = if height == 0 { 0 } else { board.0[0].len() };
+    let mut body = String::new();
+    let mut blank = 0;
+    for cell in board.0.iter().flatten() {
+        let (dir, num) = match cell {
+            Cell::Wall(WallEnum::Hint(dir, num)) => (direction_enum_to_dir(dir), *num),
+            _ => {
+                blank += 1;
+                if blank == 26 {
+                    body.push('z');
+                    blank = 0;
+                }
+                continue;
+            }
+        };
+        if blank > 0 {
+            body.push((b'a' + blank - 1) as char);
+            blank = 0;
+        }
+        match num {
+            n if n < 0 => body.push_str(&format!("{}.", dir)),
+            n if n < 16 => body.push_str(&format!("{}{:x}", dir, n)),
+            n if n < 256 => body.push_str(&format!("{}{:02x}", dir + 5, n)),
+            n => body.push_str(&format!("-{}{:03x}", dir, n)),
+        }
+    }
+    if blank > 0 {
+        body.push((b'a' + blank - 1) as char);
+    }
+    format!("{}/{}/{}", width, height, body)
+}
+
+fn direction_enum_to_dir(dir: &DirectionEnum) -> u32 {
+    match dir {
+        DirectionEnum::None => 0,
+        DirectionEnum::Up => 1,
+        DirectionEnum::Down => 2,
+        DirectionEnum::Left => 3,
+        DirectionEnum::Right => 4,
+    }
+}
+
+fn dir_to_direction_enum(dir: u32) -> DirectionEnum {
+    match dir {
+        1 => DirectionEnum::Up,
+        2 => DirectionEnum::Down,
+        3 => DirectionEnum::Left,
+        4 => DirectionEnum::Right,
+        _ => DirectionEnum::None,
+    }
+}
+
+fn dir_to_char(dir: DirectionEnum) -> char {
+    match dir {
+        DirectionEnum::Up => '↑',
+        DirectionEnum::Down => '↓',
+        DirectionEnum::Left => '←',
+        DirectionEnum::Right => '→',
+        _ => ' ',
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckResultInvalidEnum {
+    AdjacentWall(i32, i32),
+    Hint(i32, i32),
+    Line(i32, i32), // 線が途切れている、分岐している
+    MultipleLoops(i32, i32), // ループが複数ある
+    Conflict(i32, i32), // 確定済みの状態と食い違う
+    NoAnswer,
+}
+
+#[derive(Debug)]
+pub enum CheckResultEnum {
+    Valid, // 矛盾点はない
+    Invalid(CheckResultInvalidEnum), // 矛盾点がある
+    Complete, // 解けている
+}
+
+impl PartialEq for CellEnum {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (CellEnum::Wall(WallEnum::Wall), CellEnum::Wall(WallEnum::Wall))
+                | (CellEnum::Wall(WallEnum::Hint(_, _)), CellEnum::Wall(WallEnum::Hint(_, _)))
+                | (CellEnum::Space(_, _), CellEnum::Space(_, _))
+                | (CellEnum::Unknown, CellEnum::Unknown)
+        )
+    }
+}
+
+
+pub fn check(board: &Board) -> CheckResultEnum {
+    let mut complete_flag = true;
+    for (i_x, row) in board.0.iter().enumerate() {
+        let i = i_x as i32;
+        for (j_x, cell) in row.iter().enumerate() {
+            let j = j_x as i32;
+            match cell {
+                Cell::Wall(wall) => {
+                    match wall {
+                        WallEnum::Wall => {
+                            // 4方向を確認し、壁があれば矛盾
+                            if i > 0
+                                && board.0[(i - 1) as usize][j as usize] == Cell::Wall(WallEnum::Wall) {
+                                return CheckResultEnum::Invalid(CheckResultInvalidEnum::AdjacentWall(i, j));
+                            }
+                            if i + 1 < board.0.len() as i32
+                                && board.0[(i + 1) as usize][j as usize] == Cell::Wall(WallEnum::Wall) {
+                                return CheckResultEnum::Invalid(CheckResultInvalidEnum::AdjacentWall(i, j));
+                            }
+                            if j > 0
+                                && board.0[i as usize][(j - 1) as usize] == Cell::Wall(WallEnum::Wall) {
+                                return CheckResultEnum::Invalid(CheckResultInvalidEnum::AdjacentWall(i, j));
+                            }
+                            if j + 1 < row.len() as i32
+                                && board.0[i as usize][(j + 1) as usize] == Cell::Wall(WallEnum::Wall) {
+                                return CheckResultEnum::Invalid(CheckResultInvalidEnum::AdjacentWall(i, j));
+                            }
+                        }
+                        WallEnum::Hint(dir, num) => {
+                            if dir.clone() == DirectionEnum::None {
+                                continue;
+                            }
+                            let vec = dir.to_vector();
+                            let mut now = (i, j);
+                            let mut wall_count: i64 = 0;
+                            let mut unknown_count: i64 = 0;
+                            let mut prev_wall_flag = false;
+                            // breakされるまで
+                            loop {
+                                now.0 += vec.0;
+                                now.1 += vec.1;
+                                if now.0 < 0 || now.0 >= board.0.len() as i32 || now.1 < 0 || now.1 >= row.len() as i32 {
+                                    break;
+                                }
+                                if board.0[now.0 as usize][now.1 as usize] == Cell::Wall(WallEnum::Wall) {
+                                    wall_count += 1;
+                                    prev_wall_flag = true;
+                                    continue;
+                                } else if board.0[now.0 as usize][now.1 as usize] == Cell::Unknown {
+                                    if !prev_wall_flag {
+                                        unknown_count += 1;
+                                        prev_wall_flag = true
+                                    } else {
+                                        prev_wall_flag = false;
+                                    }
+                                    continue;
+                                }
+                                prev_wall_flag = false;
+                            }
+                            // 壁の数がすでにnumを超えていたら矛盾
+                            if wall_count > *num {
+                                return CheckResultEnum::Invalid(CheckResultInvalidEnum::Hint(i, j));
+                            }
+                            // 壁の数壁を置けるマスの数の合計がnumを超えていたら矛盾
+                            if wall_count + unknown_count < *num {
+                                return CheckResultEnum::Invalid(CheckResultInvalidEnum::Hint(i, j));
+                            }
+                        }
+                    }
+                }
+                Cell::Space(one, two) => {
+                    if let CheckResultEnum::Invalid(_) = check_direction_and_return_result(one, i, j, board) {
+                        return CheckResultEnum::Invalid(CheckResultInvalidEnum::Hint(i, j));
+                    }
+                    if let CheckResultEnum::Invalid(_) = check_direction_and_return_result(two, i, j, board) {
+                        return CheckResultEnum::Invalid(CheckResultInvalidEnum::Hint(i, j));
+                    }
+                    if one.is_none() || two.is_none() {
+                        complete_flag = false;
+                    }
+                }
+                Cell::Unknown => {
+                    complete_flag = false;
+                }
+            }
+        }
+    }
+
+    // 一つの線で繋がっていることのチェック
+    if complete_flag {
+        if let Some((i, j)) = find_separated_loop(board) {
+            return CheckResultEnum::Invalid(CheckResultInvalidEnum::MultipleLoops(i, j));
+        }
+        return CheckResultEnum::Complete;
+    }
+    CheckResultEnum::Valid
+}
+
+// 最初のループと繋がっていないマスを返す
+fn find_separated_loop(board: &Board) -> Option<(i32, i32)> {
+    let width = board.0[0].len();
+    let mut uf = QuickUnionUf::<UnionBySize>::new(board.0.len() * width);
+    let mut spaces = Vec::new();
+    for (i, row) in board.0.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            if let Cell::Space(one, two) = cell {
+                spaces.push((i, j));
+                for dir in [one, two].into_iter().flatten() {
+                    let vec = dir.to_vector();
+                    uf.union(i * width + j, ((i as i32 + vec.0) as usize) * width + (j as i32 + vec.1) as usize);
+                }
+            }
+        }
+    }
+    let root = uf.find(spaces.first()?.0 * width + spaces[0].1);
+    spaces.into_iter()
+        .find(|(i, j)| uf.find(i * width + j) != root)
+        .map(|(i, j)| (i as i32, j as i32))
+}
+
+fn check_direction_and_continue(direction: &Option<DirectionEnum>, next: (i32, i32), target: (i32, i32)) -> bool {
+    match direction {
+        Some(dir) => {
+            let vec = dir.to_vector();
+            let next = (next.0 + vec.0, next.1 + vec.1);
+            next == target
+        }
+        None => false,
+    }
+}
+
+fn check_direction_and_return_result(direction: &Option<DirectionEnum>, i: i32, j: i32, board: &Board) -> CheckResultEnum {
+    match direction {
+        Some(now) => {
+            let vec = now.to_vector();
+            let next = (i + vec.0, j + vec.1);
+            if next.0 < 0 || next.0 >= board.0.len() as i32 || next.1 < 0 || next.1 >= board.0[0].len() as i32 {
+                return CheckResultEnum::Invalid(CheckResultInvalidEnum::Hint(i, j));
+            }
+            match &board.0[next.0 as usize][next.1 as usize] {
+                Cell::Space(another_one, another_two) => {
+                    if check_direction_and_continue(another_one, next, (i, j)) {
+                        return CheckResultEnum::Valid;
+                    }
+                    if check_direction_and_continue(another_two, next, (i, j)) {
+                        return CheckResultEnum::Valid;
+                    }
+                    CheckResultEnum::Invalid(CheckResultInvalidEnum::Hint(i, j))
+                }
+                _ => CheckResultEnum::Invalid(CheckResultInvalidEnum::Hint(i, j)),
+            }
+        }
+        None => CheckResultEnum::Valid,
+    }
+}
+
+// 解くときに使う方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendEnum {
+    Backtrack, // マスの状態を順に試す
+    Search,    // 推論しながら探索する
+    Sat,       // CNFにしてSATソルバで解く
+    Randomized(u64), // seedで決まる順番でマスの状態を試し、やり直しながら解く
+}
+
+pub fn solve_with(board: &Board, backend: BackendEnum) -> (CheckResultEnum, Option<Board>) {
+    match backend {
+        BackendEnum::Backtrack => solve(board, &create_priority_queue(board)),
+        BackendEnum::Search => match technique::solutions(board, 1).pop() {
+            Some(b) => (CheckResultEnum::Complete, Some(b)),
+            None => (CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer), None),
+        },
+        BackendEnum::Sat => cnf::solve_sat(board),
+        BackendEnum::Randomized(seed) => solve_randomized(board, seed),
+    }
+}
+
+// 盤面には書き込まずに、マスや線の状態を仮定して解く
+// 仮定が盤面と直接食い違うときはConflict、解がないときはNoAnswer
+pub fn solve_assuming(board: &Board, backend: BackendEnum, assumptions: &[grid::FactEnum]) -> (CheckResultEnum, Option<Board>) {
+    let mut grid = match grid::Grid::from_board(board) {
+        Ok(grid) => grid,
+        Err(reason) => return (CheckResultEnum::Invalid(reason), None),
+    };
+    for fact in assumptions {
+        if let Err(reason) = grid.apply(*fact) {
+            return (CheckResultEnum::Invalid(reason), None);
+        }
+    }
+    let answer = match backend {
+        BackendEnum::Backtrack | BackendEnum::Randomized(_) => {
+            for assumed in write_assumptions(board, assumptions) {
+                let (result, answer) = solve_with(&assumed, backend);
+                if let CheckResultEnum::Complete = result {
+                    return (result, answer);
+                }
+            }
+            None
+        }
+        BackendEnum::Search => {
+            let mut solutions = Vec::new();
+            technique::search(&grid, 1, &mut solutions);
+            solutions.pop()
+        }
+        BackendEnum::Sat => match cnf::SatSession::new(board) {
+            Ok(mut session) => {
+                let lits: Vec<sat::Lit> = assumptions.iter().filter_map(|fact| session.cnf().fact_lit(*fact)).collect();
+                session.solve(&lits)
+            }
+            Err(reason) => return (CheckResultEnum::Invalid(reason), None),
+        },
+    };
+    match answer {
+        Some(grid) => (CheckResultEnum::Complete, Some(grid.to_board())),
+        None => (CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer), None),
+    }
+}
+
+// (i, j)からdirに線を引く。両端のマスに向きを足し、引けなければfalse
+fn add_line(board: &mut Board, (i, j): (usize, usize), dir: &DirectionEnum) -> bool {
+    let vec = dir.to_vector();
+    let next = (i as i32 + vec.0, j as i32 + vec.1);
+    if next.0 < 0 || next.0 >= board.0.len() as i32 || next.1 < 0 || next.1 >= board.0[0].len() as i32 {
+        return false;
+    }
+    for ((y, x), d) in [((i, j), dir.clone()), ((next.0 as usize, next.1 as usize), dir.reverse())] {
+        board.0[y][x] = match board.0[y][x].clone() {
+            Cell::Unknown | Cell::Space(None, _) => Cell::Space(Some(d), None),
+            Cell::Space(Some(one), None) if one != d => Cell::Space(Some(one), Some(d)),
+            Cell::Space(Some(one), another) if one == d || another == Some(d.clone()) => Cell::Space(Some(one), another),
+            _ => return false,
+        };
+    }
+    true
+}
+
+// 仮定を盤面に書く。線がないことは書けないので、端のマスが黒マスになるか、ほかの2方向に線が出る場合に分ける
+fn write_assumptions(board: &Board, assumptions: &[grid::FactEnum]) -> Vec<Board> {
+    let mut boards = vec![board.clone()];
+    for fact in assumptions {
+        boards = boards.into_iter().flat_map(|mut assumed| match *fact {
+            grid::FactEnum::Shaded(i, j) => {
+                assumed.0[i][j] = Cell::Wall(WallEnum::Wall);
+                vec![assumed]
+            }
+            grid::FactEnum::Unshaded(i, j) => {
+                if assumed.0[i][j] == Cell::Unknown {
+                    assumed.0[i][j] = Cell::Space(None, None);
+                }
+                vec![assumed]
+            }
+            grid::FactEnum::Line(e) => {
+                let dir = if let grid::EdgeEnum::Right(..) = e { DirectionEnum::Right } else { DirectionEnum::Down };
+                if add_line(&mut assumed, e.cells().0, &dir) { vec![assumed] } else { vec![] }
+            }
+            grid::FactEnum::Cross(e) => {
+                let (i, j) = e.cells().0;
+                let dir = if let grid::EdgeEnum::Right(..) = e { DirectionEnum::Right } else { DirectionEnum::Down };
+                let lines: Vec<DirectionEnum> = match &assumed.0[i][j] {
+                    Cell::Wall(_) => return vec![assumed],
+                    Cell::Space(Some(one), Some(another)) => return if *one == dir || *another == dir { vec![] } else { vec![assumed] },
+                    Cell::Space(Some(one), None) => vec![one.clone()],
+                    _ => vec![],
+                };
+                let mut alternatives = Vec::new();
+                if assumed.0[i][j] == Cell::Unknown {
+                    let mut shaded = assumed.clone();
+                    shaded.0[i][j] = Cell::Wall(WallEnum::Wall);
+                    alternatives.push(shaded);
+                }
+                // 線の出る向きの組。すでに一本あれば残りの一本だけ選ぶ
+                let others: Vec<&DirectionEnum> = grid::DIRECTIONS.iter().filter(|d| **d != dir && !lines.contains(d)).collect();
+                let pairs: Vec<Vec<&DirectionEnum>> = if lines.is_empty() {
+                    let others = &others;
+                    (0..others.len()).flat_map(|k| others[k + 1..].iter().map(move |second| vec![others[k], *second])).collect()
+                } else {
+                    others.iter().map(|d| vec![*d]).collect()
+                };
+                for pair in pairs {
+                    let mut next = assumed.clone();
+                    if pair.iter().all(|d| add_line(&mut next, (i, j), d)) {
+                        alternatives.push(next);
+                    }
+                }
+                alternatives
+            }
+        }).collect();
+    }
+    boards
+}
+
+pub fn solve(board: &Board, pq: &PriorityQueue<(usize, usize), i32>) -> (CheckResultEnum, Option<Board>) {
+    solve_ordered(board, pq, &mut SearchOrder::fixed())
+}
+
+// 候補を試す順番と、探索するノード数の上限
+pub struct SearchOrder {
+    rng: Option<random::Rng>,
+    budget: Option<u64>,
+    trace: Option<Vec<trace::TraceEventEnum>>,
+    pub nodes: u64,
+}
+
+impl SearchOrder {
+    // 壁、上下左右の順
+    pub fn fixed() -> SearchOrder {
+        SearchOrder { rng: None, budget: None, trace: None, nodes: 0 }
+    }
+
+    pub fn randomized(seed: u64) -> SearchOrder {
+        SearchOrder { rng: Some(random::Rng::new(seed)), budget: None, trace: None, nodes: 0 }
+    }
+
+    // 固定の順で試し、仮定や後戻りを記録する
+    pub fn traced() -> SearchOrder {
+        SearchOrder { rng: None, budget: None, trace: Some(Vec::new()), nodes: 0 }
+    }
+
+    pub fn take_trace(&mut self) -> Vec<trace::TraceEventEnum> {
+        self.trace.take().unwrap_or_default()
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.budget.map(|budget| self.nodes >= budget).unwrap_or(false)
+    }
+
+    fn arrange(&mut self, mut candidate: Vec<Cell>) -> Vec<Cell> {
+        if let Some(rng) = self.rng.as_mut() {
+            rng.shuffle(&mut candidate);
+        }
+        candidate
+    }
+
+    // 記録するときだけ盤面を複製する
+    fn record(&mut self, event: impl FnOnce() -> trace::TraceEventEnum) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(event());
+        }
+    }
+
+    // マスを決めたことと、それに伴って決まったマスを記録する
+    fn record_move(&mut self, board: &Board, new_board: &Board, cell: (usize, usize), cells: Vec<(usize, usize)>, forced: bool) {
+        self.record(|| {
+            let mut decided = board.clone();
+            decided.0[cell.0][cell.1] = new_board.0[cell.0][cell.1].clone();
+            if forced {
+                trace::TraceEventEnum::Forced { cell, board: decided }
+            } else {
+                trace::TraceEventEnum::Guess { cell, board: decided }
+            }
+        });
+        let cells: Vec<(usize, usize)> = cells.into_iter().filter(|c| *c != cell).collect();
+        if !cells.is_empty() {
+            self.record(|| trace::TraceEventEnum::Propagate { cells, board: new_board.clone() });
+        }
+    }
+}
+
+// seedで決まる順番で試し、Lubyの列で増やしたノード数を超えたら最初からやり直す
+pub fn solve_randomized(board: &Board, seed: u64) -> (CheckResultEnum, Option<Board>) {
+    let mut order = SearchOrder::randomized(seed);
+    let pq = create_priority_queue(board);
+    let mut restart = 0;
+    loop {
+        order.nodes = 0;
+        order.budget = Some(sat::luby(restart) * RESTART_NODES);
+        let (result, answer) = solve_ordered(board, &pq, &mut order);
+        if answer.is_some() || !order.exhausted() {
+            return (result, answer);
+        }
+        restart += 1;
+    }
+}
+
+const RESTART_NODES: u64 = 1000;
+
+pub fn solve_ordered(board: &Board, pq: &PriorityQueue<(usize, usize), i32>, order: &mut SearchOrder) -> (CheckResultEnum, Option<Board>) {
+    if order.exhausted() {
+        return (CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer), None);
+    }
+    order.nodes += 1;
+    let result = check(board);
+    match result {
+        CheckResultEnum::Invalid(_) => return (result, None),
+        CheckResultEnum::Complete => return (result, Some(board.clone())),
+        _ => {}
+    }
+
+    let mut next_pq = pq.clone();
+    let next = next_pq.pop();
+    if next.is_none() {
+        return (CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer), None);
+    }
+    if next.unwrap().1 == 0 {
+        return solve_ordered(board, &next_pq, order);
+    }
+
+    let i = next.unwrap().0.0 as i32;
+    let j = next.unwrap().0.1 as i32;
+    let cell = &board.0[i as usize][j as usize];
+    match cell {
+        CellEnum::Unknown => {
+            let candidate = vec![
+                Cell::Wall(WallEnum::Wall),
+                Cell::Space(Some(DirectionEnum::Up), None),
+                Cell::Space(Some(DirectionEnum::Down), None),
+                Cell::Space(Some(DirectionEnum::Left), None),
+                Cell::Space(Some(DirectionEnum::Right), None),
+            ];
+            // 置ける候補が一つしかなければ、仮定ではなく決まった手
+            let candidate = playable(board, i as usize, j as usize, candidate);
+            let forced = candidate.len() == 1;
+            for c in order.arrange(candidate) {
+                let mut new_board = board.clone();
+                let mut update_cell = Vec::new();
+                match c.clone() {
+                    Cell::Space(Some(one), _) => {
+                        let vec = one.to_vector();
+                        let next = (i + vec.0, j + vec.1);
+                        if next.0 < 0 || next.0 >= board.0.len() as i32 || next.1 < 0 || next.1 >= board.0[0].len() as i32 {
+                            continue;
+                        }
+                        match new_board.0[next.0 as usize][next.1 as usize].clone() {
+                            Cell::Unknown | Cell::Space(None, _) => {
+                                new_board.0[next.0 as usize][next.1 as usize] = Cell::Space(Some(one.reverse()), None);
+                                update_cell.push((next.0, next.1));
+                            }
+                            Cell::Space(Some(another), None) => {
+                                new_board.0[next.0 as usize][next.1 as usize] = Cell::Space(Some(another), Some(one.reverse()));
+                                update_cell.push((next.0, next.1));
+                            }
+                            _ => continue
+                        }
+                    }
+                    CellEnum::Wall(_) => {
+                        update_cell.push((i, j));
+                        // 上下左右は必ずSpace
+                        if i + 1 < board.0.len() as i32 && board.0[(i + 1) as usize][j as usize] == Cell::Unknown {
+                            new_board.0[(i + 1) as usize][j as usize] = Cell::Space(None, None);
+                            update_cell.push((i + 1, j));
+                        }
+                        if i > 0 && board.0[(i - 1) as usize][j as usize] == Cell::Unknown {
+                            new_board.0[(i - 1) as usize][j as usize] = Cell::Space(None, None);
+                            update_cell.push((i - 1, j));
+                        }
+                        if j + 1 < board.0[0].len() as i32 && board.0[i as usize][(j + 1) as usize] == Cell::Unknown {
+                            new_board.0[i as usize][(j + 1) as usize] = Cell::Space(None, None);
+                            update_cell.push((i, j + 1));
+                        }
+                        if j > 0 && board.0[i as usize][(j - 1) as usize] == Cell::Unknown {
+                            new_board.0[i as usize][(j - 1) as usize] = Cell::Space(None, None);
+                            update_cell.push((i, j - 1));
+                        }
+
+                        // 壁と壁に挟まれているのであれば、斜めはSpace
+                        // TODO 線を引いても大丈夫
+                        if i + 2 < board.0.len() as i32 {
+                            if let Cell::Wall(_) = board.0[(i + 2) as usize][j as usize].clone() {
+                                if j + 1 < board.0[0].len() as i32 && board.0[(i + 1) as usize][(j + 1) as usize] == Cell::Unknown {
+                                    new_board.0[(i + 1) as usize][(j + 1) as usize] = Cell::Space(None, None);
+                                    update_cell.push((i + 1, j + 1));
+                                }
+                                if j > 0 && board.0[(i + 1) as usize][(j - 1) as usize] == Cell::Unknown {
+                                    new_board.0[(i + 1) as usize][(j - 1) as usize] = Cell::Space(None, None);
+                                    update_cell.push((i + 1, j - 1));
+                                }
+                            }
+                        }
+                        if i + 2 == board.0.len() as i32 {
+                            if j + 1 < board.0[0].len() as i32 && board.0[(i + 1) as usize][(j + 1) as usize] == Cell::Unknown {
+                                new_board.0[(i + 1) as usize][(j + 1) as usize] = Cell::Space(None, None);
+                                update_cell.push((i + 1, j + 1));
+                            }
+                            if j > 0 && board.0[(i + 1) as usize][(j - 1) as usize] == Cell::Unknown {
+                                new_board.0[(i + 1) as usize][(j - 1) as usize] = Cell::Space(None, None);
+                                update_cell.push((i + 1, j - 1));
+                            }
+                        }
+
+                        if i - 2 >= 0 {
+                            if let Cell::Wall(_) = board.0[(i - 2) as usize][j as usize].clone() {
+                                if j + 1 < board.0[0].len() as i32 && board.0[(i - 1) as usize][(j + 1) as usize] == Cell::Unknown {
+                                    new_board.0[(i - 1) as usize][(j + 1) as usize] = Cell::Space(None, None);
+                                    update_cell.push((i - 1, j + 1));
+                                }
+                                if j > 0 && board.0[(i - 1) as usize][(j - 1) as usize] == Cell::Unknown {
+                                    new_board.0[(i - 1) as usize][(j - 1) as usize] = Cell::Space(None, None);
+                                    update_cell.push((i - 1, j - 1));
+                                }
+                            }
+                        }
+                        if i - 2 == 0 {
+                            if j + 1 < board.0[0].len() as i32 && board.0[(i - 1) as usize][(j + 1) as usize] == Cell::Unknown {
+                                new_board.0[(i - 1) as usize][(j + 1) as usize] = Cell::Space(None, None);
+                                update_cell.push((i - 1, j + 1));
+                            }
+                            if j > 0 && board.0[(i - 1) as usize][(j - 1) as usize] == Cell::Unknown {
+                                new_board.0[(i - 1) as usize][(j - 1) as usize] = Cell::Space(None, None);
+                                update_cell.push((i - 1, j - 1));
+                            }
+                        }
+
+                        if j + 2 < board.0[0].len() as i32 {
+                            if let Cell::Wall(_) = board.0[i as usize][(j + 2) as usize].clone() {
+                                if i + 1 < board.0.len() as i32 && board.0[(i + 1) as usize][(j + 1) as usize] == Cell::Unknown {
+                                    new_board.0[(i + 1) as usize][(j + 1) as usize] = Cell::Space(None, None);
+                                    update_cell.push((i + 1, j + 1));
+                                }
+                                if i > 0 && board.0[(i - 1) as usize][(j + 1) as usize] == Cell::Unknown {
+                                    new_board.0[(i - 1) as usize][(j + 1) as usize] = Cell::Space(None, None);
+                                    update_cell.push((i - 1, j + 1));
+                                }
+                            }
+                        }
+                        if j + 2 == board.0[0].len() as i32 {
+                            if i + 1 < board.0.len() as i32 && board.0[(i + 1) as usize][(j + 1) as usize] == Cell::Unknown {
+                                new_board.0[(i + 1) as usize][(j + 1) as usize] = Cell::Space(None, None);
+                                update_cell.push((i + 1, j + 1));
+                            }
+                            if i > 0 && board.0[(i - 1) as usize][(j + 1) as usize] == Cell::Unknown {
+                                new_board.0[(i - 1) as usize][(j + 1) as usize] = Cell::Space(None, None);
+                                update_cell.push((i - 1, j + 1));
+                            }
+                        }
+
+                        if j - 2 >= 0 {
+                            if let Cell::Wall(_) = board.0[i as usize][(j - 2) as usize].clone() {
+                                if i + 1 < board.0.len() as i32 && board.0[(i + 1) as usize][(j - 1) as usize] == Cell::Unknown {
+                                    new_board.0[(i + 1) as usize][(j - 1) as usize] = Cell::Space(None, None);
+                                    update_cell.push((i + 1, j - 1));
+                                }
+                                if i > 0 && board.0[(i - 1) as usize][(j - 1) as usize] == Cell::Unknown {
+                                    new_board.0[(i - 1) as usize][(j - 1) as usize] = Cell::Space(None, None);
+                                    update_cell.push((i - 1, j - 1));
+                                }
+                            }
+                        }
+                        if j - 2 == 0 {
+                            if i + 1 < board.0.len() as i32 && board.0[(i + 1) as usize][(j - 1) as usize] == Cell::Unknown {
+                                new_board.0[(i + 1) as usize][(j - 1) as usize] = Cell::Space(None, None);
+                                update_cell.push((i + 1, j - 1));
+                            }
+                            if i > 0 && board.0[(i - 1) as usize][(j - 1) as usize] == Cell::Unknown {
+                                new_board.0[(i - 1) as usize][(j - 1) as usize] = Cell::Space(None, None);
+                                update_cell.push((i - 1, j - 1));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                let mut next_candidate_pq = next_pq.clone();
+                new_board.0[i as usize][j as usize] = c;
+                order.record_move(board, &new_board, (i as usize, j as usize), update_cell.iter().map(|(y, x)| (*y as usize, *x as usize)).collect(), forced);
+                for (i, j) in update_cell {
+                    let clen = candidates(&new_board, i as usize, j as usize).len();
+                    if clen > 0 {
+                        next_candidate_pq.push((i as usize, j as usize), -(clen as i32));
+                    }
+                }
+                let (result, answer) = solve_ordered(&new_board, &next_candidate_pq, order);
+                if let CheckResultEnum::Complete = result {
+                    return (result, answer);
+                }
+                if !forced {
+                    order.record(|| trace::TraceEventEnum::Backtrack { cell: (i as usize, j as usize), board: board.clone() });
+                }
+            }
+            (CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer), None)
+        }
+        CellEnum::Space(None, _) => {
+            let candidate = vec![
+                Cell::Space(Some(DirectionEnum::Up), None),
+                Cell::Space(Some(DirectionEnum::Down), None),
+                Cell::Space(Some(DirectionEnum::Left), None),
+                Cell::Space(Some(DirectionEnum::Right), None),
+            ];
+            // 置ける候補が一つしかなければ、仮定ではなく決まった手
+            let candidate = playable(board, i as usize, j as usize, candidate);
+            let forced = candidate.len() == 1;
+            for c in order.arrange(candidate) {
+                let mut new_board = board.clone();
+                let mut update_cell = Vec::new();
+                update_cell.push((i as usize, j as usize));
+                if let Cell::Space(Some(one), _) = c.clone() {
+                    let vec = one.to_vector();
+                    let next = (i + vec.0, j + vec.1);
+                    if next.0 < 0 || next.0 >= board.0.len() as i32 || next.1 < 0 || next.1 >= board.0[0].len() as i32 {
+                        continue;
+                    }
+                    match new_board.0[next.0 as usize][next.1 as usize].clone() {
+                        Cell::Unknown | Cell::Space(None, _) => {
+                            new_board.0[next.0 as usize][next.1 as usize] = Cell::Space(Some(one.reverse()), None);
+                            update_cell.push((next.0 as usize, next.1 as usize));
+                        }
+                        Cell::Space(Some(another), None) => {
+                            new_board.0[next.0 as usize][next.1 as usize] = Cell::Space(Some(another), Some(one.reverse()));
+                            update_cell.push((next.0 as usize, next.1 as usize));
+                        }
+                        _ => continue
+                    }
+                }
+                let mut next_candidate_pq = next_pq.clone();
+                new_board.0[i as usize][j as usize] = c;
+                order.record_move(board, &new_board, (i as usize, j as usize), update_cell.clone(), forced);
+                for (i, j) in update_cell {
+                    let clen = candidates(&new_board, i , j ).len();
+                    if clen > 0 {
+                        next_candidate_pq.push((i, j), -(clen as i32));
+                    }
+                }
+                let (result, answer) = solve_ordered(&new_board, &next_candidate_pq, order);
+                if let CheckResultEnum::Complete = result {
+                    return (result, answer);
+                }
+                if !forced {
+                    order.record(|| trace::TraceEventEnum::Backtrack { cell: (i as usize, j as usize), board: board.clone() });
+                }
+            }
+            (CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer), None)
+        }
+        CellEnum::Space(Some(r), None) => {
+            let candidate = vec![
+                Cell::Space(Some(r.clone()), Some(DirectionEnum::Up)),
+                Cell::Space(Some(r.clone()), Some(DirectionEnum::Down)),
+                Cell::Space(Some(r.clone()), Some(DirectionEnum::Left)),
+                Cell::Space(Some(r.clone()), Some(DirectionEnum::Right)),
+            ];
+            // 置ける候補が一つしかなければ、仮定ではなく決まった手
+            let candidate = playable(board, i as usize, j as usize, candidate);
+            let forced = candidate.len() == 1;
+            for c in order.arrange(candidate) {
+                let mut new_board = board.clone();
+                let mut update_cell = Vec::new();
+                if let Cell::Space(Some(another), Some(one)) = c.clone() {
+                    if another == one {
+                        continue; // 同じ方向はダメ
+                    }
+                    let vec = one.to_vector();
+                    let next = (i + vec.0, j + vec.1);
+                    if next.0 < 0 || next.0 >= board.0.len() as i32 || next.1 < 0 || next.1 >= board.0[0].len() as i32 {
+                        continue;
+                    }
+                    match new_board.0[next.0 as usize][next.1 as usize].clone() {
+                        Cell::Unknown | Cell::Space(None, _) => {
+                            new_board.0[next.0 as usize][next.1 as usize] = Cell::Space(Some(one.reverse()), None);
+                            update_cell.push((next.0 as usize, next.1 as usize));
+                        }
+                        Cell::Space(Some(another), None) => {
+                            new_board.0[next.0 as usize][next.1 as usize] = Cell::Space(Some(another), Some(one.reverse()));
+                            update_cell.push((next.0 as usize, next.1 as usize));
+                        }
+                        _ => continue
+                    }
+                }
+                let mut next_candidate_pq = next_pq.clone();
+                new_board.0[i as usize][j as usize] = c;
+                order.record_move(board, &new_board, (i as usize, j as usize), update_cell.clone(), forced);
+                for (i, j) in update_cell {
+                    let clen = candidates(&new_board, i , j ).len();
+                    if clen > 0 {
+                        next_candidate_pq.push((i, j), -(clen as i32));
+                    }
+                }
+                let (result, answer) = solve_ordered(&new_board, &next_candidate_pq, order);
+                if let CheckResultEnum::Complete = result {
+                    return (result, answer);
+                }
+                if !forced {
+                    order.record(|| trace::TraceEventEnum::Backtrack { cell: (i as usize, j as usize), board: board.clone() });
+                }
+            }
+            (CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer), None)
+        }
+        _ => solve_ordered(board, &next_pq, order)
+    }
+}
+
+// 新しく伸ばす線が盤面の外や、線を受けられないマスに向かう候補を除く
+fn playable(board: &Board, i: usize, j: usize, candidate: Vec<Cell>) -> Vec<Cell> {
+    candidate.into_iter().filter(|c| {
+        let dir = match c {
+            Cell::Space(Some(one), Some(another)) if one == another => return false,
+            Cell::Space(_, Some(dir)) | Cell::Space(Some(dir), None) => dir,
+            _ => return true,
+        };
+        let vec = dir.to_vector();
+        let next = (i as i32 + vec.0, j as i32 + vec.1);
+        next.0 >= 0 && next.0 < board.0.len() as i32 && next.1 >= 0 && next.1 < board.0[0].len() as i32 &&
+            matches!(&board.0[next.0 as usize][next.1 as usize], Cell::Unknown | Cell::Space(_, None))
+    }).collect()
+}
+
+pub fn candidates(board: &Board, i: usize, j: usize) -> Vec<Cell> {
+    match &board.0[i][j] {
+        Cell::Wall(_) => vec![],
+        Cell::Space(Some(_), Some(_)) => vec![],
+        Cell::Space(Some(one), None) => {
+            [DirectionEnum::Up, DirectionEnum::Down, DirectionEnum::Left, DirectionEnum::Right]
+                .iter()
+                .filter(|dir| {
+                    dir != &one
+                })
+                .filter(|dir| {
+                    let vec = dir.to_vector();
+                    let next = (i as i32 + vec.0, j as i32 + vec.1);
+                    next.0 >= 0 && next.0 < board.0.len() as i32 && next.1 >= 0 && next.1 < board.0[0].len() as i32 &&
+                        matches!(&board.0[next.0 as usize][next.1 as usize], Cell::Unknown | Cell::Space(_, None))
+                })
+                .map(|dir| Cell::Space(Some(one.clone()), Some(dir.clone())))
+                .collect()
+        }
+        Cell::Space(None, _) => {
+            let dirs: Vec<DirectionEnum> = vec![DirectionEnum::Up, DirectionEnum::Down, DirectionEnum::Left, DirectionEnum::Right]
+                .into_iter()
+                .filter(|dir| {
+                    let vec = dir.to_vector();
+                    let next = (i as i32 + vec.0, j as i32 + vec.1);
+                    next.0 >= 0 && next.0 < board.0.len() as i32 && next.1 >= 0 && next.1 < board.0[0].len() as i32 &&
+                        matches!(&board.0[next.0 as usize][next.1 as usize], Cell::Unknown | Cell::Space(_, None))
+                }).collect();
+            dirs
+                .iter()
+                .flat_map(|item1| {
+                    dirs.iter()
+                        .filter(move |&item2| *item1 != *item2)
+                        .map(move |item2| Cell::Space(Some(item1.clone()), Some(item2.clone())))
+                })
+                .collect()
+        }
+        Cell::Unknown => {
+            let mut next_board = board.clone();
+            next_board.0[i][j] = Cell::Space(None, None);
+            let mut ret = candidates(&next_board, i, j);
+            ret.push(Cell::Wall(WallEnum::Wall));
+            ret
+        }
+    }
+}
+
+pub fn create_priority_queue(board: &Board) -> PriorityQueue<(usize, usize), i32> {
+    let mut pq = PriorityQueue::new();
+    for i in 0..board.0.len() {
+        for j in 0..board.0[0].len() {
+            let c = candidates(board, i, j);
+            if c.is_empty() {
+                continue;
+            }
+            pq.push((i, j), -(c.len() as i32));
+        }
+    }
+    pq
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hint(board: &Board, i: usize, j: usize) -> Option<(DirectionEnum, i64)> {
+        match &board.0[i][j] {
+            Cell::Wall(WallEnum::Hint(dir, num)) => Some((dir.clone(), *num)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        for problem in [
+            "2/2/d",
+            "2/5/j",
+            "5/5/g22q",
+            "10/10/202022l40i4141h40f122242l31i2131h30b42c101210c41i",
+            "10/10/23l24zg21c42n13b11l42m14c",
+            "4/1/1.c",
+            "30/1/zd",
+            "5/5/610x",
+            "5/5/-1100x",
+        ] {
+            assert_eq!(encode_board(&create_board(problem)), problem);
+        }
+    }
+
+    // 末尾の空白を省いた文字列は、省かずに書き直す
+    #[test]
+    fn encode_writes_trailing_blanks() {
+        assert_eq!(encode_board(&create_board("3/3/40")), "3/3/40h");
+    }
+
+    // 2桁のヒントは向きに5を足した文字で始まる。以前は5を引かずに読み、向きが消えていた
+    #[test]
+    fn decode_two_digit_clue_direction() {
+        let board = create_board("5/1/610a720a9ff");
+        assert_eq!(hint(&board, 0, 0), Some((DirectionEnum::Up, 16)));
+        assert_eq!(hint(&board, 0, 1), None);
+        assert_eq!(hint(&board, 0, 2), Some((DirectionEnum::Down, 32)));
+        assert_eq!(hint(&board, 0, 4), Some((DirectionEnum::Right, 255)));
+        let board = create_board("3/1/510a830");
+        assert_eq!(hint(&board, 0, 0), Some((DirectionEnum::None, 16)));
+        assert_eq!(hint(&board, 0, 2), Some((DirectionEnum::Left, 48)));
+    }
+
+    #[test]
+    fn decode_three_digit_clue() {
+        let board = create_board("2/1/-4100");
+        assert_eq!(hint(&board, 0, 0), Some((DirectionEnum::Right, 256)));
+        assert_eq!(board.0[0][1], Cell::Unknown);
+    }
+
+    fn backends() -> [BackendEnum; 4] {
+        [BackendEnum::Backtrack, BackendEnum::Search, BackendEnum::Sat, BackendEnum::Randomized(7)]
+    }
+
+    // どの解き方でも、仮定を満たす同じ解か、同じ失敗を返す
+    #[test]
+    fn backends_agree_under_assumptions() {
+        use grid::{EdgeEnum, FactEnum};
+        let board = create_board("5/5/g22q");
+        let solution = cnf::sat_solutions(&board, 1).pop().unwrap();
+        let assumptions = [FactEnum::Shaded(2, 2), FactEnum::Line(EdgeEnum::Right(0, 0)), FactEnum::Cross(EdgeEnum::Down(0, 1))];
+        for backend in backends() {
+            let (result, answer) = solve_assuming(&board, backend, &assumptions);
+            assert!(matches!(result, CheckResultEnum::Complete));
+            let answer = answer.unwrap();
+            assert!(diff::diff(&answer, &solution).unwrap().is_empty());
+            let grid = grid::Grid::from_board(&answer).unwrap();
+            assert!(assumptions[..2].iter().all(|fact| grid.holds(*fact) == Some(true)));
+        }
+
+        // 解にある線を消すと解がなくなる
+        for backend in backends() {
+            let (result, answer) = solve_assuming(&board, backend, &[FactEnum::Cross(EdgeEnum::Right(0, 0))]);
+            assert!(matches!(result, CheckResultEnum::Invalid(CheckResultInvalidEnum::NoAnswer)));
+            assert!(answer.is_none());
+        }
+
+        // 同じ線を引いて消すのは、解く前に食い違う
+        let conflicting = [FactEnum::Line(EdgeEnum::Right(0, 0)), FactEnum::Cross(EdgeEnum::Right(0, 0))];
+        for backend in backends() {
+            let (result, answer) = solve_assuming(&board, backend, &conflicting);
+            assert!(matches!(result, CheckResultEnum::Invalid(CheckResultInvalidEnum::Conflict(..))));
+            assert!(answer.is_none());
+        }
+    }
+
+    // 同じseedなら同じ順番で探し、同じ数のノードを訪れる。答えは固定の順で解いたものと同じ
+    #[test]
+    fn randomized_is_reproducible() {
+        let board = create_board("5/5/g22q");
+        let pq = create_priority_queue(&board);
+        let (_, expected) = solve(&board, &pq);
+        let expected = expected.unwrap();
+        for seed in [1, 2] {
+            let runs: Vec<(u64, Option<Board>)> = (0..2).map(|_| {
+                let mut order = SearchOrder::randomized(seed);
+                let (result, answer) = solve_ordered(&board, &pq, &mut order);
+                assert!(matches!(result, CheckResultEnum::Complete));
+                (order.nodes, answer)
+            }).collect();
+            assert_eq!(runs[0].0, runs[1].0);
+            for (_, answer) in runs.iter() {
+                assert!(diff::diff(answer.as_ref().unwrap(), &expected).unwrap().is_empty());
+            }
+            let (result, answer) = solve_randomized(&board, seed);
+            assert!(matches!(result, CheckResultEnum::Complete));
+            assert!(diff::diff(&answer.unwrap(), &expected).unwrap().is_empty());
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{Board, DirectionEnum};
+use crate::random::Rng;
+use crate::session::{EditSession, UniquenessEnum};
+use crate::symmetry::SymmetryEnum;
+
+// ヒントを弱める方法。強いものから順に試す
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReductionEnum {
+    Remove,       // ヒントのマスをなくす
+    QuestionMark, // 数字を?にする
+    Blank,        // 向きも数字もないヒントにする
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderEnum {
+    Greedy,      // 左上から順に
+    Random(u64), // seedで決まる順に
+}
+
+pub struct Minimized {
+    pub board: Board,
+    pub solution: Board,
+    pub removed: usize,
+    pub lowered: usize,
+    pub checks: usize,
+}
+
+fn reduce(clue: &(DirectionEnum, i64), reduction: ReductionEnum) -> Option<Option<(DirectionEnum, i64)>> {
+    match reduction {
+        ReductionEnum::Remove => Some(None),
+        ReductionEnum::QuestionMark if clue.0 != DirectionEnum::None && clue.1 >= 0 => Some(Some((clue.0.clone(), -2))),
+        ReductionEnum::Blank if clue.0 != DirectionEnum::None || clue.1 >= 0 => Some(Some((DirectionEnum::None, -2))),
+        _ => None,
+    }
+}
+
+// 解が一つのままになるようにヒントを弱めていき、どのヒントもそれ以上弱められなくなるまで繰り返す
+pub fn minimize(board: &Board, order: OrderEnum, reductions: &[ReductionEnum]) -> Option<Minimized> {
+    minimize_symmetric(board, order, reductions, SymmetryEnum::None)
+}
+
+// 対称な位置にあるヒントはまとめて弱める
+pub fn minimize_symmetric(board: &Board, order: OrderEnum, reductions: &[ReductionEnum], symmetry: SymmetryEnum) -> Option<Minimized> {
+    let height = board.0.len();
+    let width = if height == 0 { 0 } else { board.0[0].len() };
+    if !symmetry.fits(height, width) {
+        return None;
+    }
+    let mut session = EditSession::new(board);
+    let mut checks = 1;
+    let mut solution = match session.check() {
+        UniquenessEnum::Unique(solution) => solution,
+        _ => return None,
+    };
+    let mut rng = match order {
+        OrderEnum::Greedy => None,
+        OrderEnum::Random(seed) => Some(Rng::new(seed)),
+    };
+    let (mut removed, mut lowered) = (0, 0);
+    loop {
+        let mut groups: Vec<Vec<(usize, usize)>> = Vec::new();
+        for i in 0..height {
+            for j in 0..width {
+                if session.clue(i, j).is_none() || groups.iter().any(|group| group.contains(&(i, j))) {
+                    continue;
+                }
+                groups.push(symmetry.orbit(i, j, height, width).into_iter().filter(|(y, x)| session.clue(*y, *x).is_some()).collect());
+            }
+        }
+        if let Some(rng) = rng.as_mut() {
+            rng.shuffle(&mut groups);
+        }
+
+        let mut changed = false;
+        for group in groups {
+            let clues: Vec<(DirectionEnum, i64)> = group.iter().map(|(i, j)| session.clue(*i, *j).cloned().unwrap()).collect();
+            for reduction in reductions {
+                let mut count = 0;
+                for ((i, j), clue) in group.iter().zip(clues.iter()) {
+                    if let Some(next) = reduce(clue, *reduction) {
+                        session.set_clue(*i, *j, next);
+                        count += 1;
+                    }
+                }
+                if count == 0 {
+                    continue;
+                }
+                checks += 1;
+                if let UniquenessEnum::Unique(next) = session.check() {
+                    solution = next;
+                    if *reduction == ReductionEnum::Remove {
+                        removed += count;
+                    } else {
+                        lowered += count;
+                    }
+                    changed = true;
+                    break;
+                }
+                for ((i, j), clue) in group.iter().zip(clues.iter()) {
+                    session.set_clue(*i, *j, Some(clue.clone()));
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    Some(Minimized { board: session.board(), solution, removed, lowered, checks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::sat_solutions;
+    use crate::{create_board, encode_board, Cell, WallEnum};
+
+    const SAMPLE: &str = "10/10/23l24zg21c42n13b11l42m14c";
+
+    // 解は一つのままで、残ったヒントはどれを消しても解が一つに決まらない
+    // 消したマスはループが通るしかないので、解がなくなることもある
+    #[test]
+    fn result_is_unique_and_locally_minimal() {
+        let minimized = minimize(&create_board(SAMPLE), OrderEnum::Greedy, &[ReductionEnum::Remove]).unwrap();
+        assert_eq!(sat_solutions(&minimized.board, 2).len(), 1);
+        for i in 0..10 {
+            for j in 0..10 {
+                if let Cell::Wall(WallEnum::Hint(..)) = minimized.board.0[i][j] {
+                    let mut board = minimized.board.clone();
+                    board.0[i][j] = Cell::Unknown;
+                    assert_ne!(sat_solutions(&board, 2).len(), 1, "clue at ({}, {}) can be removed", i, j);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_order_is_reproducible() {
+        let reductions = [ReductionEnum::Remove, ReductionEnum::QuestionMark];
+        let first = minimize(&create_board(SAMPLE), OrderEnum::Random(5), &reductions).unwrap();
+        let second = minimize(&create_board(SAMPLE), OrderEnum::Random(5), &reductions).unwrap();
+        assert_eq!(encode_board(&first.board), encode_board(&second.board));
+        assert_eq!((first.removed, first.lowered, first.checks), (second.removed, second.lowered, second.checks));
+    }
+
+    // 解がないか一つに決まらない盤面は扱わない
+    #[test]
+    fn rejects_boards_without_a_unique_solution() {
+        assert!(minimize(&create_board("3/3/"), OrderEnum::Greedy, &[ReductionEnum::Remove]).is_none());
+        assert!(minimize(&create_board("5/5/g23q"), OrderEnum::Greedy, &[ReductionEnum::Remove]).is_none());
+    }
+}
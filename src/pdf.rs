@@ -0,0 +1,306 @@
+use std::fmt::Write;
+use std::io;
+use crate::Board;
+use crate::png::zlib;
+use crate::rating::DifficultyEnum;
+use crate::render::{layout, ModeEnum, RenderOptions, Rgb, ShapeEnum, ThemeEnum};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageSizeEnum {
+    A4,
+    Letter,
+}
+
+impl PageSizeEnum {
+    // ポイント単位の幅と高さ
+    pub fn size(&self) -> (f64, f64) {
+        match self {
+            PageSizeEnum::A4 => (595.28, 841.89),
+            PageSizeEnum::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+pub struct SheetPuzzle {
+    pub id: String,
+    pub title: String,
+    pub difficulty: Option<DifficultyEnum>,
+    pub board: Board,
+    pub solution: Option<Board>, // あれば解答のページに載せる
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SheetOptions {
+    pub page: PageSizeEnum,
+    pub columns: usize,
+    pub rows: usize,
+    pub answer_columns: usize, // 解答は小さくして、1ページに多く並べる
+    pub answer_rows: usize,
+    pub theme: ThemeEnum,
+}
+
+impl Default for SheetOptions {
+    fn default() -> SheetOptions {
+        SheetOptions { page: PageSizeEnum::A4, columns: 2, rows: 2, answer_columns: 3, answer_rows: 4, theme: ThemeEnum::Print }
+    }
+}
+
+const MARGIN: f64 = 36.0;
+const HEADER: f64 = 24.0;
+const LABEL: f64 = 10.0;
+
+// Helveticaで書ける文字だけにして、括弧などをエスケープする
+fn pdf_string(text: &str) -> String {
+    let mut out = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+// Helveticaの' 'から'~'までの字幅。1000で1em
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+// pdf_stringで書いたときの幅。書けない文字は'?'になる
+fn text_width(text: &str, size: f64) -> f64 {
+    text.chars().map(|c| match c {
+        ' '..='~' => HELVETICA[c as usize - 32] as f64,
+        _ => HELVETICA[b'?' as usize - 32] as f64,
+    }).sum::<f64>() * size / 1000.0
+}
+
+fn color(color: Rgb) -> String {
+    format!("{:.3} {:.3} {:.3}", color.0 as f64 / 255.0, color.1 as f64 / 255.0, color.2 as f64 / 255.0)
+}
+
+// 透明度ごとにExtGStateを一つ作る
+fn opacity_state(opacities: &mut Vec<f64>, opacity: f64) -> String {
+    let index = match opacities.iter().position(|o| *o == opacity) {
+        Some(index) => index,
+        None => {
+            opacities.push(opacity);
+            opacities.len() - 1
+        }
+    };
+    format!("/A{} gs", index)
+}
+
+struct Page {
+    content: String,
+    opacities: Vec<f64>,
+}
+
+impl Page {
+    fn text(&mut self, x: f64, y: f64, size: f64, text: &str) {
+        writeln!(self.content, "0 g BT /F1 {:.2} Tf {:.2} {:.2} Td {} Tj ET", size, x, y, pdf_string(text)).unwrap();
+    }
+
+    // 盤面を(left, top)から、マスの大きさcellで描く。ページの座標は下が原点なので上下を返す
+    fn board(&mut self, board: &Board, left: f64, top: f64, cell: f64, mode: ModeEnum, theme: ThemeEnum) {
+        let scene = layout(board, &RenderOptions { mode, cell_size: cell, theme });
+        let p = |(x, y): (f64, f64)| (left + x, top - y);
+        let out = &mut self.content;
+        writeln!(out, "q").unwrap();
+        for shape in scene.shapes.iter() {
+            match shape {
+                ShapeEnum::Rect { x, y, w, h, color: c, opacity } => {
+                    let (x0, y0) = p((*x, y + h));
+                    writeln!(out, "{} {} rg {:.2} {:.2} {:.2} {:.2} re f", opacity_state(&mut self.opacities, *opacity), color(*c), x0, y0, w, h).unwrap();
+                }
+                ShapeEnum::Line { x1, y1, x2, y2, width, color: c } => {
+                    let (a, b) = (p((*x1, *y1)), p((*x2, *y2)));
+                    writeln!(out, "{} 2 J {} RG {:.2} w {:.2} {:.2} m {:.2} {:.2} l S", opacity_state(&mut self.opacities, 1.0), color(*c), width, a.0, a.1, b.0, b.1).unwrap();
+                }
+                ShapeEnum::Polyline { points, closed, width, color: c, opacity } => {
+                    write!(out, "{} 1 J 1 j {} RG {:.2} w", opacity_state(&mut self.opacities, *opacity), color(*c), width).unwrap();
+                    for (k, point) in points.iter().enumerate() {
+                        let (x, y) = p(*point);
+                        write!(out, " {:.2} {:.2} {}", x, y, if k == 0 { "m" } else { "l" }).unwrap();
+                    }
+                    writeln!(out, " {}", if *closed { "h S" } else { "S" }).unwrap();
+                }
+                ShapeEnum::Polygon { points, color: c } => {
+                    write!(out, "{} {} rg", opacity_state(&mut self.opacities, 1.0), color(*c)).unwrap();
+                    for (k, point) in points.iter().enumerate() {
+                        let (x, y) = p(*point);
+                        write!(out, " {:.2} {:.2} {}", x, y, if k == 0 { "m" } else { "l" }).unwrap();
+                    }
+                    writeln!(out, " h f").unwrap();
+                }
+                ShapeEnum::Circle { x, y, r, color: c, opacity } => {
+                    // 4本のベジェ曲線で近似する
+                    let (cx, cy) = p((*x, *y));
+                    let k = r * 0.5523;
+                    writeln!(out, "{} {} rg {:.2} {:.2} m {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c f",
+                        opacity_state(&mut self.opacities, *opacity), color(*c),
+                        cx + r, cy,
+                        cx + r, cy + k, cx + k, cy + r, cx, cy + r,
+                        cx - k, cy + r, cx - r, cy + k, cx - r, cy,
+                        cx - r, cy - k, cx - k, cy - r, cx, cy - r,
+                        cx + k, cy - r, cx + r, cy - k, cx + r, cy).unwrap();
+                }
+                ShapeEnum::Text { x, y, size, text, color: c } => {
+                    let (cx, cy) = p((*x, *y));
+                    let width = text_width(text, *size);
+                    writeln!(out, "{} {} rg BT /F1 {:.2} Tf {:.2} {:.2} Td {} Tj ET", opacity_state(&mut self.opacities, 1.0), color(*c), size, cx - width / 2.0, cy - size * 0.36, pdf_string(text)).unwrap();
+                }
+            }
+        }
+        writeln!(out, "Q").unwrap();
+    }
+}
+
+fn size_of(board: &Board) -> (usize, usize) {
+    let height = board.0.len();
+    (if height == 0 { 0 } else { board.0[0].len() }, height)
+}
+
+// 枠に収まるマスの大きさ。layoutは上下左右に半マスの余白を付ける
+fn fit(board: &Board, width: f64, height: f64) -> f64 {
+    let (w, h) = size_of(board);
+    (width / (w as f64 + 1.0)).min(height / (h as f64 + 1.0))
+}
+
+fn slots(options: &SheetOptions, columns: usize, rows: usize) -> Vec<(f64, f64, f64, f64)> {
+    let (page_width, page_height) = options.page.size();
+    let slot_width = (page_width - MARGIN * 2.0) / columns as f64;
+    let slot_height = (page_height - MARGIN * 2.0 - HEADER) / rows as f64;
+    let mut result = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            result.push((MARGIN + column as f64 * slot_width, page_height - MARGIN - HEADER - row as f64 * slot_height, slot_width, slot_height));
+        }
+    }
+    result
+}
+
+fn header(page: &mut Page, options: &SheetOptions, text: &str) {
+    let (_, page_height) = options.page.size();
+    page.text(MARGIN, page_height - MARGIN - 14.0, 14.0, text);
+}
+
+// 問題のページと、小さく並べた解答のページを作る
+pub fn sheets(puzzles: &[SheetPuzzle], options: &SheetOptions) -> Vec<u8> {
+    let mut pages: Vec<Page> = Vec::new();
+    let per_page = (options.columns * options.rows).max(1);
+    let layout_slots = slots(options, options.columns.max(1), options.rows.max(1));
+    let count = puzzles.chunks(per_page).len();
+    for (number, chunk) in puzzles.chunks(per_page).enumerate() {
+        let mut page = Page { content: String::new(), opacities: Vec::new() };
+        header(&mut page, options, &format!("Yajilin  {}/{}", number + 1, count));
+        for (puzzle, (left, top, width, height)) in chunk.iter().zip(layout_slots.iter()) {
+            let title = match puzzle.difficulty {
+                Some(difficulty) => format!("{}  [{}]", puzzle.title, difficulty),
+                None => puzzle.title.clone(),
+            };
+            page.text(left + 4.0, top - 14.0, 12.0, &title);
+            page.text(left + 4.0, top - height + 6.0, 9.0, &format!("#{}", puzzle.id));
+            let cell = fit(&puzzle.board, width - 8.0, height - 20.0 - LABEL - 8.0);
+            let (w, _) = size_of(&puzzle.board);
+            let board_left = left + (width - cell * (w as f64 + 1.0)) / 2.0;
+            page.board(&puzzle.board, board_left, top - 20.0, cell, ModeEnum::Puzzle, options.theme);
+        }
+        pages.push(page);
+    }
+
+    let answers: Vec<&SheetPuzzle> = puzzles.iter().filter(|puzzle| puzzle.solution.is_some()).collect();
+    let per_page = (options.answer_columns * options.answer_rows).max(1);
+    let answer_slots = slots(options, options.answer_columns.max(1), options.answer_rows.max(1));
+    let count = answers.chunks(per_page).len();
+    for (number, chunk) in answers.chunks(per_page).enumerate() {
+        let mut page = Page { content: String::new(), opacities: Vec::new() };
+        header(&mut page, options, &format!("Answers  {}/{}", number + 1, count));
+        for (puzzle, (left, top, width, height)) in chunk.iter().zip(answer_slots.iter()) {
+            let solution = puzzle.solution.as_ref().unwrap();
+            page.text(left + 4.0, top - 10.0, 8.0, &format!("#{}", puzzle.id));
+            let cell = fit(solution, width - 8.0, height - 14.0);
+            let (w, _) = size_of(solution);
+            let board_left = left + (width - cell * (w as f64 + 1.0)) / 2.0;
+            page.board(solution, board_left, top - 12.0, cell, ModeEnum::Solution, options.theme);
+        }
+        pages.push(page);
+    }
+    // 問題がなくても、ページのない文書にはしない
+    if pages.is_empty() {
+        let mut page = Page { content: String::new(), opacities: Vec::new() };
+        header(&mut page, options, "Yajilin");
+        pages.push(page);
+    }
+    write_document(&pages, options.page.size())
+}
+
+// 1 カタログ、2 ページ一覧、3 フォント、その後にページと内容を交互に置く
+fn write_document(pages: &[Page], (width, height): (f64, f64)) -> Vec<u8> {
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = (0..pages.len()).map(|k| format!("{} 0 R", 4 + k * 2)).collect();
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+    for (k, page) in pages.iter().enumerate() {
+        let states: Vec<String> = page.opacities.iter().enumerate().map(|(n, o)| format!("/A{} << /CA {} /ca {} >>", n, o, o)).collect();
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 3 0 R >> /ExtGState << {} >> >> /Contents {} 0 R >>",
+            width, height, states.join(" "), 5 + k * 2
+        ).into_bytes());
+        let data = zlib(page.content.as_bytes());
+        let mut stream = format!("<< /Length {} /Filter /FlateDecode >>\nstream\n", data.len()).into_bytes();
+        stream.extend(data);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (k, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", k + 1).into_bytes());
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        writeln!(table, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(table, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).unwrap();
+    out.extend(table.into_bytes());
+    out
+}
+
+pub fn write_sheets(path: &str, puzzles: &[SheetPuzzle], options: &SheetOptions) -> io::Result<()> {
+    std::fs::write(path, sheets(puzzles, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_sheets_have_one_page() {
+        let pdf = String::from_utf8_lossy(&sheets(&[], &SheetOptions::default())).into_owned();
+        assert!(pdf.contains("/Kids [4 0 R] /Count 1"));
+    }
+
+    #[test]
+    fn text_width_uses_glyph_widths() {
+        assert!((text_width("12", 10.0) - 11.12).abs() < 1e-9);
+        assert!((text_width("?", 10.0) - 5.56).abs() < 1e-9);
+        assert!((text_width("Wi", 10.0) - 11.66).abs() < 1e-9);
+        assert_eq!(text_width("→", 10.0), text_width("?", 10.0));
+    }
+}
@@ -0,0 +1,546 @@
+use std::io;
+use crate::Board;
+use crate::render::{layout, RenderOptions, Rgb, Scene, ShapeEnum};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PngOptions {
+    pub dpi: f64, // 96で、cell_sizeがそのまま画素数になる
+    pub transparent: bool,
+}
+
+impl Default for PngOptions {
+    fn default() -> PngOptions {
+        PngOptions { dpi: 96.0, transparent: false }
+    }
+}
+
+// 5x7のビットマップ。各行の下位5ビットを左から使う
+fn glyph(c: char) -> Option<[u8; 7]> {
+    Some(match c {
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        '?' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+        '↑' => [0x04, 0x0e, 0x15, 0x04, 0x04, 0x04, 0x04],
+        '↓' => [0x04, 0x04, 0x04, 0x04, 0x15, 0x0e, 0x04],
+        '←' => [0x00, 0x04, 0x08, 0x1f, 0x08, 0x04, 0x00],
+        '→' => [0x00, 0x04, 0x02, 0x1f, 0x02, 0x04, 0x00],
+        ' ' => [0; 7],
+        _ => return None,
+    })
+}
+
+// 1画素をSAMPLES x SAMPLES点で調べて、縁をなめらかにする
+const SAMPLES: usize = 4;
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f64; 4]>, // 0から1のRGBA
+}
+
+impl Canvas {
+    // 図形に含まれるかをinsideで調べ、含まれる割合だけ色を重ねる
+    fn fill(&mut self, bounds: (f64, f64, f64, f64), color: Rgb, opacity: f64, inside: impl Fn(f64, f64) -> bool) {
+        let mut mask = Mask::new(self, bounds);
+        mask.add(bounds, &inside);
+        self.blend(&mask, color, opacity);
+    }
+
+    fn blend(&mut self, mask: &Mask, color: Rgb, opacity: f64) {
+        let rgb = [color.0 as f64 / 255.0, color.1 as f64 / 255.0, color.2 as f64 / 255.0];
+        for py in mask.y0..mask.y1 {
+            for px in mask.x0..mask.x1 {
+                let hits = mask.hits(px, py);
+                if hits == 0 {
+                    continue;
+                }
+                let alpha = opacity * hits as f64 / (SAMPLES * SAMPLES) as f64;
+                let pixel = &mut self.pixels[py * self.width + px];
+                let out = alpha + pixel[3] * (1.0 - alpha);
+                for k in 0..3 {
+                    pixel[k] = if out > 0.0 { (rgb[k] * alpha + pixel[k] * pixel[3] * (1.0 - alpha)) / out } else { 0.0 };
+                }
+                pixel[3] = out;
+            }
+        }
+    }
+}
+
+// 図形が覆う点。いくつかの部分に分けて調べても、重なった所を二重に塗らない
+struct Mask {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+    samples: Vec<bool>,
+}
+
+impl Mask {
+    fn new(canvas: &Canvas, bounds: (f64, f64, f64, f64)) -> Mask {
+        let x0 = (bounds.0.floor().max(0.0) as usize).min(canvas.width);
+        let y0 = (bounds.1.floor().max(0.0) as usize).min(canvas.height);
+        let x1 = (bounds.2.ceil().max(0.0) as usize).clamp(x0, canvas.width);
+        let y1 = (bounds.3.ceil().max(0.0) as usize).clamp(y0, canvas.height);
+        Mask { x0, y0, x1, y1, samples: vec![false; (x1 - x0) * (y1 - y0) * SAMPLES * SAMPLES] }
+    }
+
+    // boundsの範囲だけ調べる
+    fn add(&mut self, bounds: (f64, f64, f64, f64), inside: &impl Fn(f64, f64) -> bool) {
+        let x0 = (bounds.0.floor().max(0.0) as usize).clamp(self.x0, self.x1);
+        let y0 = (bounds.1.floor().max(0.0) as usize).clamp(self.y0, self.y1);
+        let x1 = (bounds.2.ceil().max(0.0) as usize).clamp(x0, self.x1);
+        let y1 = (bounds.3.ceil().max(0.0) as usize).clamp(y0, self.y1);
+        let stride = (self.x1 - self.x0) * SAMPLES;
+        for py in y0..y1 {
+            for px in x0..x1 {
+                for sy in 0..SAMPLES {
+                    for sx in 0..SAMPLES {
+                        let index = ((py - self.y0) * SAMPLES + sy) * stride + (px - self.x0) * SAMPLES + sx;
+                        if self.samples[index] {
+                            continue;
+                        }
+                        let x = px as f64 + (sx as f64 + 0.5) / SAMPLES as f64;
+                        let y = py as f64 + (sy as f64 + 0.5) / SAMPLES as f64;
+                        self.samples[index] = inside(x, y);
+                    }
+                }
+            }
+        }
+    }
+
+    fn hits(&self, px: usize, py: usize) -> usize {
+        let stride = (self.x1 - self.x0) * SAMPLES;
+        (0..SAMPLES).map(|sy| {
+            let start = ((py - self.y0) * SAMPLES + sy) * stride + (px - self.x0) * SAMPLES;
+            self.samples[start..start + SAMPLES].iter().filter(|s| **s).count()
+        }).sum()
+    }
+}
+
+// 点から線分までの距離
+fn distance(x: f64, y: f64, a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 { 0.0 } else { (((x - a.0) * dx + (y - a.1) * dy) / length).clamp(0.0, 1.0) };
+    let (nx, ny) = (a.0 + t * dx - x, a.1 + t * dy - y);
+    (nx * nx + ny * ny).sqrt()
+}
+
+fn bounds(points: &[(f64, f64)], pad: f64) -> (f64, f64, f64, f64) {
+    let x0 = points.iter().map(|p| p.0).fold(f64::MAX, f64::min);
+    let y0 = points.iter().map(|p| p.1).fold(f64::MAX, f64::min);
+    let x1 = points.iter().map(|p| p.0).fold(f64::MIN, f64::max);
+    let y1 = points.iter().map(|p| p.1).fold(f64::MIN, f64::max);
+    (x0 - pad, y0 - pad, x1 + pad, y1 + pad)
+}
+
+fn rasterize(scene: &Scene, scale: f64, transparent: bool) -> Canvas {
+    let width = (scene.width * scale).round().max(1.0) as usize;
+    let height = (scene.height * scale).round().max(1.0) as usize;
+    let background = scene.background;
+    let mut canvas = Canvas { width, height, pixels: vec![[0.0; 4]; width * height] };
+    if !transparent {
+        canvas.fill((0.0, 0.0, width as f64, height as f64), background, 1.0, |_, _| true);
+    }
+    let p = |(x, y): (f64, f64)| (x * scale, y * scale);
+    for shape in scene.shapes.iter() {
+        match shape {
+            ShapeEnum::Rect { x, y, w, h, color, opacity } => {
+                let (x0, y0) = p((*x, *y));
+                let (x1, y1) = p((x + w, y + h));
+                canvas.fill((x0, y0, x1, y1), *color, *opacity, |x, y| x >= x0 && x < x1 && y >= y0 && y < y1);
+            }
+            ShapeEnum::Line { x1, y1, x2, y2, width, color } => {
+                // svgと同じく端を線幅の半分だけ伸ばす
+                let (a, b) = (p((*x1, *y1)), p((*x2, *y2)));
+                let half = width * scale / 2.0;
+                let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt().max(1e-9);
+                let (ux, uy) = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+                canvas.fill(bounds(&[a, b], half * 1.5), *color, 1.0, |x, y| {
+                    let along = (x - a.0) * ux + (y - a.1) * uy;
+                    let across = (-(x - a.0) * uy + (y - a.1) * ux).abs();
+                    along >= -half && along <= length + half && across <= half
+                });
+            }
+            ShapeEnum::Polyline { points, closed, width, color, opacity } => {
+                // 継ぎ目を丸めた線分をまとめて一つの図形として塗るので、重なっても濃くならない
+                let points: Vec<(f64, f64)> = points.iter().map(|q| p(*q)).collect();
+                let mut segments: Vec<((f64, f64), (f64, f64))> = points.windows(2).map(|w| (w[0], w[1])).collect();
+                if *closed && points.len() > 2 {
+                    segments.push((points[points.len() - 1], points[0]));
+                }
+                let half = width * scale / 2.0;
+                let mut mask = Mask::new(&canvas, bounds(&points, half + 1.0));
+                for (a, b) in segments {
+                    mask.add(bounds(&[a, b], half + 1.0), &|x, y| distance(x, y, a, b) <= half);
+                }
+                canvas.blend(&mask, *color, *opacity);
+            }
+            ShapeEnum::Polygon { points, color } => {
+                let points: Vec<(f64, f64)> = points.iter().map(|q| p(*q)).collect();
+                canvas.fill(bounds(&points, 1.0), *color, 1.0, |x, y| {
+                    let mut inside = false;
+                    for k in 0..points.len() {
+                        let (a, b) = (points[k], points[(k + 1) % points.len()]);
+                        if (a.1 > y) != (b.1 > y) && x < a.0 + (y - a.1) * (b.0 - a.0) / (b.1 - a.1) {
+                            inside = !inside;
+                        }
+                    }
+                    inside
+                });
+            }
+            ShapeEnum::Circle { x, y, r, color, opacity } => {
+                let (cx, cy) = p((*x, *y));
+                let r = r * scale;
+                canvas.fill((cx - r, cy - r, cx + r, cy + r), *color, *opacity, |x, y| (x - cx).powi(2) + (y - cy).powi(2) <= r * r);
+            }
+            ShapeEnum::Text { x, y, size, text, color } => {
+                // 数字の高さを文字の大きさの7割にする
+                let glyphs: Vec<[u8; 7]> = text.chars().map(|c| glyph(c).unwrap_or([0x1f; 7])).collect();
+                let dot = size * scale * 0.7 / 7.0;
+                let total = (glyphs.len() * 6) as f64 * dot - dot;
+                let (cx, cy) = p((*x, *y));
+                let (left, top) = (cx - total / 2.0, cy - 3.5 * dot);
+                canvas.fill((left, top, left + total, top + 7.0 * dot), *color, 1.0, |x, y| {
+                    let column = ((x - left) / dot).floor();
+                    let row = ((y - top) / dot).floor();
+                    if column < 0.0 || !(0.0..7.0).contains(&row) {
+                        return false;
+                    }
+                    let (column, row) = (column as usize, row as usize);
+                    let (index, bit) = (column / 6, column % 6);
+                    bit < 5 && index < glyphs.len() && glyphs[index][row] & (0x10 >> bit) != 0
+                });
+            }
+        }
+    }
+    canvas
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    // 下位ビットから順に書く
+    fn bits(&mut self, value: u32, count: u32) {
+        self.buffer |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // ハフマン符号は上位ビットから書く
+    fn code(&mut self, code: u32, length: u32) {
+        let reversed = code.reverse_bits() >> (32 - length);
+        self.bits(reversed, length);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+const LENGTH_BASE: [u32; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u32; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u32; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA: [u32; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+fn literal(writer: &mut BitWriter, value: u32) {
+    match value {
+        0..=143 => writer.code(0x30 + value, 8),
+        144..=255 => writer.code(0x190 + value - 144, 9),
+        256..=279 => writer.code(value - 256, 7),
+        _ => writer.code(0xc0 + value - 280, 8),
+    }
+}
+
+// 固定ハフマン符号の1ブロックで圧縮する。一致はハッシュの連鎖で探す
+fn deflate(data: &[u8]) -> Vec<u8> {
+    const WINDOW: usize = 32768;
+    const CHAIN: usize = 64;
+    let mut writer = BitWriter { out: Vec::new(), buffer: 0, count: 0 };
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+    let hash = |p: usize| ((data[p] as usize) << 10 ^ (data[p + 1] as usize) << 5 ^ data[p + 2] as usize) & 0x7fff;
+    let mut head = vec![usize::MAX; 0x8000];
+    let mut previous = vec![usize::MAX; data.len()];
+    let mut p = 0;
+    let insert = |head: &mut Vec<usize>, previous: &mut Vec<usize>, q: usize| {
+        if q + 2 < data.len() {
+            let h = hash(q);
+            previous[q] = head[h];
+            head[h] = q;
+        }
+    };
+    while p < data.len() {
+        let (mut best_length, mut best_distance) = (0, 0);
+        if p + 2 < data.len() {
+            let mut candidate = head[hash(p)];
+            let mut steps = 0;
+            while candidate != usize::MAX && p - candidate <= WINDOW && steps < CHAIN {
+                let mut length = 0;
+                while length < 258 && p + length < data.len() && data[candidate + length] == data[p + length] {
+                    length += 1;
+                }
+                if length > best_length {
+                    best_length = length;
+                    best_distance = p - candidate;
+                }
+                candidate = previous[candidate];
+                steps += 1;
+            }
+        }
+        if best_length >= 3 {
+            let code = LENGTH_BASE.iter().rposition(|base| *base as usize <= best_length).unwrap();
+            literal(&mut writer, 257 + code as u32);
+            writer.bits(best_length as u32 - LENGTH_BASE[code], LENGTH_EXTRA[code]);
+            let code = DISTANCE_BASE.iter().rposition(|base| *base as usize <= best_distance).unwrap();
+            writer.code(code as u32, 5);
+            writer.bits(best_distance as u32 - DISTANCE_BASE[code], DISTANCE_EXTRA[code]);
+            for q in p..p + best_length {
+                insert(&mut head, &mut previous, q);
+            }
+            p += best_length;
+        } else {
+            literal(&mut writer, data[p] as u32);
+            insert(&mut head, &mut previous, p);
+            p += 1;
+        }
+    }
+    literal(&mut writer, 256);
+    writer.finish()
+}
+
+pub(crate) fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    out.extend(deflate(data));
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let mut body = kind.to_vec();
+    body.extend_from_slice(data);
+    out.extend(&body);
+    out.extend(crc32(&body).to_be_bytes());
+}
+
+fn encode(canvas: &Canvas, dpi: f64) -> Vec<u8> {
+    // 各行の前に、左の画素との差を取るフィルタ(Sub)の番号を置く
+    let mut raw = Vec::with_capacity((canvas.width * 4 + 1) * canvas.height);
+    for row in canvas.pixels.chunks(canvas.width) {
+        raw.push(1);
+        let bytes: Vec<u8> = row.iter().flat_map(|pixel| pixel.map(|v| (v * 255.0).round().clamp(0.0, 255.0) as u8)).collect();
+        for k in 0..bytes.len() {
+            raw.push(bytes[k].wrapping_sub(if k >= 4 { bytes[k - 4] } else { 0 }));
+        }
+    }
+    let mut out = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    let mut header = Vec::new();
+    header.extend((canvas.width as u32).to_be_bytes());
+    header.extend((canvas.height as u32).to_be_bytes());
+    header.extend([8, 6, 0, 0, 0]);
+    chunk(&mut out, b"IHDR", &header);
+    let per_metre = ((dpi / 0.0254).round() as u32).to_be_bytes();
+    let mut physical = Vec::new();
+    physical.extend(per_metre);
+    physical.extend(per_metre);
+    physical.push(1);
+    chunk(&mut out, b"pHYs", &physical);
+    chunk(&mut out, b"IDAT", &zlib(&raw));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+// svgと同じ配置を画素にする。dpiに合わせて拡大する
+pub fn scene_to_png(scene: &Scene, options: &PngOptions) -> Vec<u8> {
+    let canvas = rasterize(scene, options.dpi / 96.0, options.transparent);
+    encode(&canvas, options.dpi)
+}
+
+pub fn to_png(board: &Board, options: &RenderOptions, png: &PngOptions) -> Vec<u8> {
+    scene_to_png(&layout(board, options), png)
+}
+
+pub fn write_png(path: &str, board: &Board, options: &RenderOptions, png: &PngOptions) -> io::Result<()> {
+    std::fs::write(path, to_png(board, options, png))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_board;
+
+    struct BitReader<'a> {
+        data: &'a [u8],
+        position: usize,
+    }
+
+    impl BitReader<'_> {
+        fn bit(&mut self) -> u32 {
+            let value = (self.data[self.position / 8] >> (self.position % 8)) & 1;
+            self.position += 1;
+            value as u32
+        }
+
+        // 下位ビットから順に読む
+        fn bits(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |acc, k| acc | self.bit() << k)
+        }
+
+        // ハフマン符号は上位ビットから読む
+        fn code(&mut self, length: u32) -> u32 {
+            (0..length).fold(0, |acc, _| acc << 1 | self.bit())
+        }
+    }
+
+    // 固定ハフマン符号のブロックだけを読む、テスト用の伸長
+    fn inflate(data: &[u8]) -> Vec<u8> {
+        let mut reader = BitReader { data, position: 0 };
+        let mut out: Vec<u8> = Vec::new();
+        loop {
+            let last = reader.bit();
+            assert_eq!(reader.bits(2), 1);
+            loop {
+                let code = reader.code(7);
+                let value = if code <= 0x17 {
+                    256 + code
+                } else {
+                    let code = code << 1 | reader.bit();
+                    match code {
+                        0x30..=0xbf => code - 0x30,
+                        0xc0..=0xc7 => 280 + code - 0xc0,
+                        _ => 144 + (code << 1 | reader.bit()) - 0x190,
+                    }
+                };
+                match value {
+                    0..=255 => out.push(value as u8),
+                    256 => break,
+                    _ => {
+                        let index = (value - 257) as usize;
+                        let length = LENGTH_BASE[index] + reader.bits(LENGTH_EXTRA[index]);
+                        let index = reader.code(5) as usize;
+                        let distance = DISTANCE_BASE[index] + reader.bits(DISTANCE_EXTRA[index]);
+                        for _ in 0..length {
+                            out.push(out[out.len() - distance as usize]);
+                        }
+                    }
+                }
+            }
+            if last == 1 {
+                return out;
+            }
+        }
+    }
+
+    fn unzlib(data: &[u8]) -> Vec<u8> {
+        assert_eq!(data[0] & 0x0f, 8);
+        assert_eq!(((data[0] as u32) << 8 | data[1] as u32) % 31, 0);
+        let out = inflate(&data[2..data.len() - 4]);
+        assert_eq!(data[data.len() - 4..], adler32(&out).to_be_bytes());
+        out
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+        // 5552バイトごとに剰余を取っても、一度に計算したものと同じ
+        let long: Vec<u8> = (0..20000).map(|k| (k * 7 % 251) as u8).collect();
+        let (a, b) = long.iter().fold((1u64, 0u64), |(a, b), byte| ((a + *byte as u64) % 65521, (b + a + *byte as u64) % 65521));
+        assert_eq!(adler32(&long), (b << 16 | a) as u32);
+    }
+
+    #[test]
+    fn zlib_round_trip() {
+        let mut seed = 1u32;
+        let noise: Vec<u8> = (0..70000).map(|_| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as u8
+        }).collect();
+        // 32768バイトより前と同じ並びを置いて、窓の端での一致も確かめる
+        let mut repeated = noise[..40000].to_vec();
+        repeated.extend_from_slice(&noise[5000..9000]);
+        let cases: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            b"a".to_vec(),
+            b"abcabcabcabcabcabc".to_vec(),
+            vec![0; 1000],
+            (0..=255).collect(),
+            noise,
+            repeated,
+        ];
+        for data in cases {
+            assert_eq!(unzlib(&zlib(&data)), data);
+        }
+    }
+
+    #[test]
+    fn png_chunks() {
+        let board = create_board("5/5/g22q");
+        let options = RenderOptions::default();
+        let png = to_png(&board, &options, &PngOptions::default());
+        assert_eq!(png[..8], [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+        let mut chunks: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+        let mut p = 8;
+        while p < png.len() {
+            let length = u32::from_be_bytes(png[p..p + 4].try_into().unwrap()) as usize;
+            let body = &png[p + 4..p + 8 + length];
+            assert_eq!(png[p + 8 + length..p + 12 + length], crc32(body).to_be_bytes());
+            chunks.push((body[..4].try_into().unwrap(), body[4..].to_vec()));
+            p += 12 + length;
+        }
+        let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"pHYs", b"IDAT", b"IEND"]);
+        let width = u32::from_be_bytes(chunks[0].1[0..4].try_into().unwrap()) as usize;
+        let height = u32::from_be_bytes(chunks[0].1[4..8].try_into().unwrap()) as usize;
+        assert_eq!(width, (options.cell_size * 6.0).round() as usize);
+        let raw = unzlib(&chunks[2].1);
+        assert_eq!(raw.len(), height * (width * 4 + 1));
+        assert!(raw.chunks(width * 4 + 1).all(|row| row[0] == 1));
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+use crate::{Board, CheckResultEnum, CheckResultInvalidEnum};
+use crate::cnf::sat_solutions;
+use crate::technique::{explain, TechniqueEnum};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DifficultyEnum {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl DifficultyEnum {
+    pub fn from_score(score: u32) -> DifficultyEnum {
+        match score {
+            0..=24 => DifficultyEnum::Easy,
+            25..=44 => DifficultyEnum::Medium,
+            45..=64 => DifficultyEnum::Hard,
+            _ => DifficultyEnum::Expert,
+        }
+    }
+
+    // この難易度になる点数の範囲
+    pub fn range(&self) -> (u32, u32) {
+        match self {
+            DifficultyEnum::Easy => (0, 24),
+            DifficultyEnum::Medium => (25, 44),
+            DifficultyEnum::Hard => (45, 64),
+            DifficultyEnum::Expert => (65, u32::MAX),
+        }
+    }
+}
+
+impl fmt::Display for DifficultyEnum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DifficultyEnum::Easy => write!(f, "easy"),
+            DifficultyEnum::Medium => write!(f, "medium"),
+            DifficultyEnum::Hard => write!(f, "hard"),
+            DifficultyEnum::Expert => write!(f, "expert"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RatingErrorEnum {
+    Invalid(CheckResultInvalidEnum),
+    MultipleSolutions, // 解が一つに決まらない問題は評価しない
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub score: u32,
+    pub label: DifficultyEnum,
+    pub steps: usize,
+    pub techniques: Vec<(TechniqueEnum, usize)>, // 解き方ごとの使用回数
+    pub hardest: TechniqueEnum,
+    pub hardest_count: usize,
+    pub depth: usize, // 仮定の最大の深さ
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} ({})", self.label, self.score)?;
+        writeln!(f, "steps: {}", self.steps)?;
+        for (technique, count) in self.techniques.iter() {
+            writeln!(f, "  {}: {}", technique, count)?;
+        }
+        writeln!(f, "hardest: {} x{}", self.hardest, self.hardest_count)?;
+        write!(f, "depth: {}", self.depth)
+    }
+}
+
+// 解き方ごとの重み
+fn weight(technique: TechniqueEnum) -> u32 {
+    match technique {
+        TechniqueEnum::ShadedNeighbours | TechniqueEnum::LineCompletion => 0,
+        TechniqueEnum::ClueRunSaturation | TechniqueEnum::CornerForcing => 1,
+        TechniqueEnum::DeadEndAvoidance => 2,
+        TechniqueEnum::NoSmallLoops => 3,
+        TechniqueEnum::Parity => 4,
+        TechniqueEnum::TrialAndError => 6,
+        TechniqueEnum::Guess | TechniqueEnum::Backtrack => 8,
+    }
+}
+
+// 解き方から難しさを見積もる。同じ盤面なら必ず同じ点数になる
+// explainは仮定で解の一つにたどり着くので、先に解が一つだけか確かめる
+pub fn rate(board: &Board) -> Result<Rating, RatingErrorEnum> {
+    if sat_solutions(board, 2).len() > 1 {
+        return Err(RatingErrorEnum::MultipleSolutions);
+    }
+    let explanation = explain(board);
+    if let CheckResultEnum::Invalid(reason) = explanation.result {
+        return Err(RatingErrorEnum::Invalid(reason));
+    }
+
+    let mut techniques: Vec<(TechniqueEnum, usize)> = Vec::new();
+    for step in explanation.steps.iter() {
+        match techniques.iter_mut().find(|(t, _)| *t == step.technique) {
+            Some((_, count)) => *count += 1,
+            None => techniques.push((step.technique, 1)),
+        }
+    }
+    techniques.sort();
+    let (hardest, hardest_count) = techniques.last().cloned().unwrap_or((TechniqueEnum::ShadedNeighbours, 0));
+    let depth = explanation.steps.iter().map(|step| step.depth).max().unwrap_or(0);
+    let steps = explanation.steps.len();
+
+    // 一番難しい解き方、その回数、手数、仮定の深さの順に効く
+    let score = weight(hardest) * 8
+        + (usize::BITS - hardest_count.leading_zeros()) * 3
+        + (steps as u32 / 25).min(10)
+        + depth as u32 * 5;
+    Ok(Rating { score, label: DifficultyEnum::from_score(score), steps, techniques, hardest, hardest_count, depth })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_board;
+
+    #[test]
+    fn pinned_score() {
+        let rating = rate(&create_board("5/5/g22q")).unwrap();
+        assert_eq!((rating.label, rating.score), (DifficultyEnum::Hard, 60));
+        assert_eq!((rating.steps, rating.hardest, rating.hardest_count, rating.depth), (41, TechniqueEnum::TrialAndError, 2, 1));
+        assert_eq!(rate(&create_board("5/5/g22q")).unwrap(), rating);
+    }
+
+    #[test]
+    fn rejects_puzzles_without_a_unique_solution() {
+        assert_eq!(rate(&create_board("3/3/")), Err(RatingErrorEnum::MultipleSolutions));
+        assert_eq!(rate(&create_board("1/1/")), Err(RatingErrorEnum::Invalid(CheckResultInvalidEnum::NoAnswer)));
+    }
+
+    #[test]
+    fn score_bands() {
+        for label in [DifficultyEnum::Easy, DifficultyEnum::Medium, DifficultyEnum::Hard, DifficultyEnum::Expert] {
+            let (low, high) = label.range();
+            assert_eq!(DifficultyEnum::from_score(low), label);
+            assert_eq!(DifficultyEnum::from_score(high), label);
+        }
+    }
+}
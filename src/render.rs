@@ -0,0 +1,281 @@
+use crate::{Board, Cell, DirectionEnum, WallEnum};
+
+// 何を描くか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModeEnum {
+    Puzzle,   // ヒントだけ
+    Solution, // 黒マスと線も
+    Overlay,  // 問題の上に、答えを薄い色で重ねる
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeEnum {
+    Light,
+    Dark,
+    Print, // 白黒で印刷する用
+}
+
+pub type Rgb = (u8, u8, u8);
+
+pub struct Palette {
+    pub background: Rgb,
+    pub grid: Rgb,
+    pub clue: Rgb,
+    pub clue_fill: Rgb,
+    pub shaded: Rgb,
+    pub line: Rgb,
+    pub overlay: Rgb,
+    pub highlight: Rgb,
+}
+
+impl ThemeEnum {
+    pub fn palette(&self) -> Palette {
+        match self {
+            ThemeEnum::Light => Palette {
+                background: (255, 255, 255),
+                grid: (153, 153, 153),
+                clue: (0, 0, 0),
+                clue_fill: (238, 238, 238),
+                shaded: (34, 34, 34),
+                line: (0, 153, 0),
+                overlay: (51, 102, 255),
+                highlight: (255, 204, 0),
+            },
+            ThemeEnum::Dark => Palette {
+                background: (30, 30, 30),
+                grid: (90, 90, 90),
+                clue: (230, 230, 230),
+                clue_fill: (50, 50, 50),
+                shaded: (200, 200, 200),
+                line: (102, 204, 102),
+                overlay: (120, 160, 255),
+                highlight: (200, 150, 0),
+            },
+            ThemeEnum::Print => Palette {
+                background: (255, 255, 255),
+                grid: (0, 0, 0),
+                clue: (0, 0, 0),
+                clue_fill: (255, 255, 255),
+                shaded: (0, 0, 0),
+                line: (0, 0, 0),
+                overlay: (128, 128, 128),
+                highlight: (200, 200, 200),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    pub mode: ModeEnum,
+    pub cell_size: f64,
+    pub theme: ThemeEnum,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions { mode: ModeEnum::Solution, cell_size: 36.0, theme: ThemeEnum::Light }
+    }
+}
+
+// 描く図形。座標は左上を原点とする画素単位
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapeEnum {
+    Rect { x: f64, y: f64, w: f64, h: f64, color: Rgb, opacity: f64 },
+    Line { x1: f64, y1: f64, x2: f64, y2: f64, width: f64, color: Rgb },
+    Polyline { points: Vec<(f64, f64)>, closed: bool, width: f64, color: Rgb, opacity: f64 },
+    Polygon { points: Vec<(f64, f64)>, color: Rgb },
+    Circle { x: f64, y: f64, r: f64, color: Rgb, opacity: f64 },
+    Text { x: f64, y: f64, size: f64, text: String, color: Rgb }, // (x, y)は文字の中心
+}
+
+pub struct Scene {
+    pub width: f64,
+    pub height: f64,
+    pub background: Rgb,
+    pub shapes: Vec<ShapeEnum>,
+}
+
+pub(crate) fn neighbour(i: usize, j: usize, dir: &DirectionEnum, height: usize, width: usize) -> Option<(usize, usize)> {
+    match dir {
+        DirectionEnum::Up if i > 0 => Some((i - 1, j)),
+        DirectionEnum::Down if i + 1 < height => Some((i + 1, j)),
+        DirectionEnum::Left if j > 0 => Some((i, j - 1)),
+        DirectionEnum::Right if j + 1 < width => Some((i, j + 1)),
+        _ => None,
+    }
+}
+
+// 線を繋いで、マスの列にする。端のあるものを先に、残りは閉じたループとして返す
+pub fn loop_paths(board: &Board) -> Vec<(Vec<(usize, usize)>, bool)> {
+    let height = board.0.len();
+    let width = if height == 0 { 0 } else { board.0[0].len() };
+    let mut adjacent: Vec<Vec<usize>> = vec![Vec::new(); height * width];
+    for i in 0..height {
+        for j in 0..width {
+            if let Cell::Space(one, another) = &board.0[i][j] {
+                for dir in one.iter().chain(another.iter()) {
+                    if let Some((y, x)) = neighbour(i, j, dir, height, width) {
+                        let (a, b) = (i * width + j, y * width + x);
+                        if !adjacent[a].contains(&b) {
+                            adjacent[a].push(b);
+                            adjacent[b].push(a);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut used = vec![false; height * width];
+    let mut paths = Vec::new();
+    let starts: Vec<usize> = (0..height * width).filter(|k| adjacent[*k].len() == 1)
+        .chain((0..height * width).filter(|k| adjacent[*k].len() > 1))
+        .collect();
+    for start in starts {
+        if used[start] {
+            continue;
+        }
+        let mut path = vec![start];
+        used[start] = true;
+        let mut now = start;
+        while let Some(next) = adjacent[now].iter().find(|k| !used[**k]).cloned() {
+            used[next] = true;
+            path.push(next);
+            now = next;
+        }
+        let closed = path.len() > 2 && adjacent[now].contains(&start);
+        paths.push((path.into_iter().map(|k| (k / width, k % width)).collect(), closed));
+    }
+    paths
+}
+
+fn clue_text(num: i64) -> String {
+    if num < 0 { "?".to_string() } else { num.to_string() }
+}
+
+// 矢印。(x, y)から向きの方へlengthだけ伸ばす
+fn arrow(shapes: &mut Vec<ShapeEnum>, x: f64, y: f64, dir: &DirectionEnum, length: f64, color: Rgb) {
+    let (dx, dy) = match dir {
+        DirectionEnum::Up => (0.0, -1.0),
+        DirectionEnum::Down => (0.0, 1.0),
+        DirectionEnum::Left => (-1.0, 0.0),
+        DirectionEnum::Right => (1.0, 0.0),
+        DirectionEnum::None => return,
+    };
+    let (x1, y1) = (x - dx * length / 2.0, y - dy * length / 2.0);
+    let (x2, y2) = (x + dx * length / 2.0, y + dy * length / 2.0);
+    let head = length * 0.4;
+    shapes.push(ShapeEnum::Line { x1, y1, x2: x2 - dx * head * 0.5, y2: y2 - dy * head * 0.5, width: length * 0.1, color });
+    shapes.push(ShapeEnum::Polygon {
+        points: vec![
+            (x2, y2),
+            (x2 - dx * head - dy * head * 0.5, y2 - dy * head + dx * head * 0.5),
+            (x2 - dx * head + dy * head * 0.5, y2 - dy * head - dx * head * 0.5),
+        ],
+        color,
+    });
+}
+
+// ヒントのマス。pzprと同じく、上下の矢印は数字の左、左右の矢印は数字の上に置く
+pub(crate) fn clue(shapes: &mut Vec<ShapeEnum>, x: f64, y: f64, size: f64, dir: &DirectionEnum, num: i64, color: Rgb) {
+    let text = clue_text(num);
+    match dir {
+        DirectionEnum::Up | DirectionEnum::Down => {
+            arrow(shapes, x + size * 0.25, y + size * 0.5, dir, size * 0.6, color);
+            shapes.push(ShapeEnum::Text { x: x + size * 0.62, y: y + size * 0.5, size: size * 0.6, text, color });
+        }
+        DirectionEnum::Left | DirectionEnum::Right => {
+            arrow(shapes, x + size * 0.5, y + size * 0.25, dir, size * 0.6, color);
+            shapes.push(ShapeEnum::Text { x: x + size * 0.5, y: y + size * 0.65, size: size * 0.55, text, color });
+        }
+        DirectionEnum::None if num >= 0 => {
+            shapes.push(ShapeEnum::Text { x: x + size * 0.5, y: y + size * 0.5, size: size * 0.65, text, color });
+        }
+        DirectionEnum::None => {}
+    }
+}
+
+// 盤面を図形の列にする。どの出力形式もこの配置で描く
+pub fn layout(board: &Board, options: &RenderOptions) -> Scene {
+    layout_highlighted(board, options, &[])
+}
+
+// 指定したマスに色を付けて描く。解き方の説明で、手筋が使ったマスを示すのに使う
+pub fn layout_highlighted(board: &Board, options: &RenderOptions, highlights: &[(usize, usize)]) -> Scene {
+    let palette = options.theme.palette();
+    let size = options.cell_size;
+    let height = board.0.len();
+    let width = if height == 0 { 0 } else { board.0[0].len() };
+    let margin = size * 0.5;
+    let mut shapes = Vec::new();
+    let (show, color, opacity) = match options.mode {
+        ModeEnum::Puzzle => (false, palette.shaded, 1.0),
+        ModeEnum::Solution => (true, palette.shaded, 1.0),
+        ModeEnum::Overlay => (true, palette.overlay, 0.5),
+    };
+
+    for (i, row) in board.0.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            let (x, y) = (margin + j as f64 * size, margin + i as f64 * size);
+            match cell {
+                Cell::Wall(WallEnum::Hint(..)) => {
+                    shapes.push(ShapeEnum::Rect { x, y, w: size, h: size, color: palette.clue_fill, opacity: 1.0 });
+                }
+                Cell::Wall(WallEnum::Wall) if show => {
+                    shapes.push(ShapeEnum::Rect { x, y, w: size, h: size, color, opacity });
+                }
+                Cell::Space(None, None) if show => {
+                    shapes.push(ShapeEnum::Circle { x: x + size * 0.5, y: y + size * 0.5, r: size * 0.06, color: palette.grid, opacity });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (i, j) in highlights.iter().filter(|(i, j)| *i < height && *j < width) {
+        let (x, y) = (margin + *j as f64 * size, margin + *i as f64 * size);
+        shapes.push(ShapeEnum::Rect { x, y, w: size, h: size, color: palette.highlight, opacity: 0.6 });
+    }
+
+    // 罫線
+    let thin = (size / 30.0).max(1.0);
+    for i in 0..=height {
+        let y = margin + i as f64 * size;
+        shapes.push(ShapeEnum::Line { x1: margin, y1: y, x2: margin + width as f64 * size, y2: y, width: thin, color: palette.grid });
+    }
+    for j in 0..=width {
+        let x = margin + j as f64 * size;
+        shapes.push(ShapeEnum::Line { x1: x, y1: margin, x2: x, y2: margin + height as f64 * size, width: thin, color: palette.grid });
+    }
+    shapes.push(ShapeEnum::Polyline {
+        points: vec![(margin, margin), (margin + width as f64 * size, margin), (margin + width as f64 * size, margin + height as f64 * size), (margin, margin + height as f64 * size)],
+        closed: true,
+        width: thin * 3.0,
+        color: palette.clue,
+        opacity: 1.0,
+    });
+
+    for (i, row) in board.0.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            if let Cell::Wall(WallEnum::Hint(dir, num)) = cell {
+                clue(&mut shapes, margin + j as f64 * size, margin + i as f64 * size, size, dir, *num, palette.clue);
+            }
+        }
+    }
+
+    if show {
+        let line = if options.mode == ModeEnum::Overlay { palette.overlay } else { palette.line };
+        for (path, closed) in loop_paths(board) {
+            let points = path.iter().map(|(i, j)| (margin + (*j as f64 + 0.5) * size, margin + (*i as f64 + 0.5) * size)).collect();
+            shapes.push(ShapeEnum::Polyline { points, closed, width: size * 0.12, color: line, opacity });
+        }
+    }
+
+    Scene {
+        width: margin * 2.0 + width as f64 * size,
+        height: margin * 2.0 + height as f64 * size,
+        background: palette.background,
+        shapes,
+    }
+}
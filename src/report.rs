@@ -0,0 +1,144 @@
+use std::fmt::Write;
+use std::io;
+use std::time::Instant;
+use crate::{clear_board, encode_board, Board, CheckResultEnum};
+use crate::grid::Grid;
+use crate::rating::rate;
+use crate::render::{layout_highlighted, ModeEnum, RenderOptions, ThemeEnum};
+use crate::session::{EditSession, UniquenessEnum};
+use crate::svg::{scene_to_svg, to_svg};
+use crate::technique::{explain, TechniqueEnum};
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+h1{font-size:1.4em}table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:2px 8px;text-align:left}\
+.boards{display:flex;gap:2em;flex-wrap:wrap}.step{display:flex;gap:1em;align-items:center;border-top:1px solid #eee;padding:4px 0}\
+.guess{color:#b60}.backtrack{color:#c00}.fallback{font-style:italic;color:#666}";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn row(out: &mut String, name: &str, value: &str) {
+    writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", escape(name), escape(value)).unwrap();
+}
+
+// 1問分の報告。画像はすべてsvgで埋め込むので、このファイルだけで開ける
+pub fn report(board: &Board, title: &str) -> String {
+    let puzzle = clear_board(board);
+    let options = RenderOptions { mode: ModeEnum::Solution, cell_size: 32.0, theme: ThemeEnum::Light };
+    let step_options = RenderOptions { cell_size: 20.0, ..options };
+
+    let start = Instant::now();
+    let mut session = EditSession::new(&puzzle);
+    let uniqueness = session.check();
+    let uniqueness_time = start.elapsed();
+    let start = Instant::now();
+    let explanation = explain(&puzzle);
+    let explain_time = start.elapsed();
+    let rating = rate(&puzzle);
+
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>", escape(title), STYLE).unwrap();
+    writeln!(out, "<h1>{}</h1>", escape(title)).unwrap();
+
+    let (status, solution) = match &uniqueness {
+        UniquenessEnum::Unique(solution) => ("unique".to_string(), Some(solution)),
+        UniquenessEnum::Multiple(first, _) => ("multiple solutions".to_string(), Some(first)),
+        UniquenessEnum::NoAnswer => ("no solution".to_string(), None),
+    };
+    writeln!(out, "<div class=\"boards\"><figure>{}<figcaption>puzzle</figcaption></figure>", to_svg(&puzzle, &RenderOptions { mode: ModeEnum::Puzzle, ..options })).unwrap();
+    if let Some(solution) = solution {
+        writeln!(out, "<figure>{}<figcaption>solution</figcaption></figure>", to_svg(solution, &options)).unwrap();
+    }
+    if let UniquenessEnum::Multiple(_, second) = &uniqueness {
+        writeln!(out, "<figure>{}<figcaption>another solution</figcaption></figure>", to_svg(second, &options)).unwrap();
+    }
+    writeln!(out, "</div>").unwrap();
+
+    writeln!(out, "<h2>Summary</h2><table>").unwrap();
+    row(&mut out, "pzpr", &encode_board(&puzzle));
+    row(&mut out, "uniqueness", &status);
+    match &rating {
+        Ok(rating) => {
+            row(&mut out, "difficulty", &format!("{} ({})", rating.label, rating.score));
+            row(&mut out, "hardest technique", &format!("{} x{}", rating.hardest, rating.hardest_count));
+            row(&mut out, "guess depth", &rating.depth.to_string());
+            for (technique, count) in rating.techniques.iter() {
+                row(&mut out, &format!("  {}", technique), &count.to_string());
+            }
+        }
+        Err(reason) => row(&mut out, "difficulty", &format!("not rated: {:?}", reason)),
+    }
+    row(&mut out, "steps", &explanation.steps.len().to_string());
+    row(&mut out, "deduction time", &format!("{:?}", explain_time));
+    row(&mut out, "uniqueness check", &format!("{:?}, {} SAT calls", uniqueness_time, session.solves));
+    let result = match &explanation.result {
+        CheckResultEnum::Complete => "solved".to_string(),
+        other => format!("{:?}", other),
+    };
+    row(&mut out, "deduction result", &result);
+    writeln!(out, "</table>").unwrap();
+
+    // 一手ずつ盤面を進めて、変わったマスに色を付ける
+    writeln!(out, "<h2>Steps</h2>").unwrap();
+    let mut grid = match Grid::from_board(&puzzle) {
+        Ok(grid) => grid,
+        Err(_) => {
+            writeln!(out, "</body></html>").unwrap();
+            return out;
+        }
+    };
+    let mut fallback = false;
+    for (k, step) in explanation.steps.iter().enumerate() {
+        if step.technique >= TechniqueEnum::TrialAndError && !fallback {
+            writeln!(out, "<p class=\"fallback\">techniques ran out, falling back to trial and error</p>").unwrap();
+            fallback = true;
+        }
+        for fact in step.changes.iter() {
+            if grid.apply(*fact).is_err() {
+                break;
+            }
+        }
+        let class = match step.technique {
+            TechniqueEnum::Guess => " guess",
+            TechniqueEnum::Backtrack => " backtrack",
+            _ => "",
+        };
+        let scene = layout_highlighted(&grid.to_board(), &step_options, &step.cells());
+        writeln!(out, "<div class=\"step{}\">{}<div><b>{}.</b> {}<br><small>{}</small></div></div>", class, scene_to_svg(&scene), k + 1, escape(&step.to_string()), escape(step.technique.description())).unwrap();
+    }
+    writeln!(out, "</body></html>").unwrap();
+    out
+}
+
+pub fn write_report(path: &str, board: &Board, title: &str) -> io::Result<()> {
+    std::fs::write(path, report(board, title))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_board;
+
+    #[test]
+    fn unique_puzzle() {
+        let board = create_board("5/5/g22q");
+        let html = report(&board, "sample <1>");
+        assert!(html.contains("<title>sample &lt;1&gt;</title>"));
+        assert!(html.contains("<tr><th>uniqueness</th><td>unique</td></tr>"));
+        assert!(html.contains("<tr><th>difficulty</th><td>hard (60)</td></tr>"));
+        assert_eq!(html.matches("<div class=\"step").count(), explain(&board).steps.len());
+        assert!(html.ends_with("</body></html>\n"));
+    }
+
+    #[test]
+    fn board_without_solution() {
+        for problem in ["5/5/g23q", "1/1/"] {
+            let html = report(&create_board(problem), problem);
+            assert!(html.contains("<tr><th>uniqueness</th><td>no solution</td></tr>"));
+            assert!(html.contains("<tr><th>difficulty</th><td>not rated"));
+            assert!(!html.contains("<figcaption>solution</figcaption>"));
+            assert!(html.ends_with("</body></html>\n"));
+        }
+    }
+}
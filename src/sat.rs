@@ -0,0 +1,507 @@
+// CDCLのSATソルバ。リテラルはDIMACSと同じく、変数番号(1から)の正負で表す
+pub type Lit = i32;
+
+// 内部ではリテラルを 2 * 変数 + 否定 の番号で持つ
+fn code(lit: Lit) -> usize {
+    (lit.unsigned_abs() as usize - 1) * 2 + (lit < 0) as usize
+}
+
+fn value(assigns: &[i8], c: usize) -> i8 {
+    if c & 1 == 0 { assigns[c >> 1] } else { -assigns[c >> 1] }
+}
+
+// 活動値の大きい変数を取り出すためのヒープ
+struct VarHeap {
+    heap: Vec<usize>,
+    indices: Vec<Option<usize>>,
+}
+
+impl VarHeap {
+    fn contains(&self, v: usize) -> bool {
+        self.indices[v].is_some()
+    }
+
+    fn up(&mut self, mut k: usize, activity: &[f64]) {
+        let v = self.heap[k];
+        while k > 0 {
+            let parent = (k - 1) / 2;
+            if activity[self.heap[parent]] >= activity[v] {
+                break;
+            }
+            self.heap[k] = self.heap[parent];
+            self.indices[self.heap[k]] = Some(k);
+            k = parent;
+        }
+        self.heap[k] = v;
+        self.indices[v] = Some(k);
+    }
+
+    fn down(&mut self, mut k: usize, activity: &[f64]) {
+        let v = self.heap[k];
+        loop {
+            let left = 2 * k + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+            let child = if left + 1 < self.heap.len() && activity[self.heap[left + 1]] > activity[self.heap[left]] { left + 1 } else { left };
+            if activity[self.heap[child]] <= activity[v] {
+                break;
+            }
+            self.heap[k] = self.heap[child];
+            self.indices[self.heap[k]] = Some(k);
+            k = child;
+        }
+        self.heap[k] = v;
+        self.indices[v] = Some(k);
+    }
+
+    fn insert(&mut self, v: usize, activity: &[f64]) {
+        if self.contains(v) {
+            return;
+        }
+        self.heap.push(v);
+        self.up(self.heap.len() - 1, activity);
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        let top = *self.heap.first()?;
+        let last = self.heap.pop().unwrap();
+        self.indices[top] = None;
+        if !self.heap.is_empty() {
+            self.heap[0] = last;
+            self.down(0, activity);
+        }
+        Some(top)
+    }
+}
+
+pub fn luby(mut x: u64) -> u64 {
+    let mut size = 1;
+    let mut seq = 0;
+    while size < x + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != x {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        x %= size;
+    }
+    1 << seq
+}
+
+pub struct Solver {
+    clauses: Vec<Vec<usize>>,
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<i8>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    trail: Vec<usize>,
+    trail_lim: Vec<usize>,
+    qhead: usize,
+    activity: Vec<f64>,
+    var_inc: f64,
+    heap: VarHeap,
+    polarity: Vec<bool>,
+    seen: Vec<bool>,
+    model: Vec<bool>,
+    ok: bool,
+    pub conflicts: u64,
+    pub decisions: u64,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            assigns: Vec::new(),
+            level: Vec::new(),
+            reason: Vec::new(),
+            trail: Vec::new(),
+            trail_lim: Vec::new(),
+            qhead: 0,
+            activity: Vec::new(),
+            var_inc: 1.0,
+            heap: VarHeap { heap: Vec::new(), indices: Vec::new() },
+            polarity: Vec::new(),
+            seen: Vec::new(),
+            model: Vec::new(),
+            ok: true,
+            conflicts: 0,
+            decisions: 0,
+        }
+    }
+
+    // 変数を決めるときに最初に試す値。解いている間に覚えた値で上書きされる
+    pub fn set_phase(&mut self, lit: Lit) {
+        self.polarity[lit.unsigned_abs() as usize - 1] = lit < 0;
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.assigns.len()
+    }
+
+    pub fn new_var(&mut self) -> Lit {
+        let v = self.assigns.len();
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.assigns.push(0);
+        self.level.push(0);
+        self.reason.push(None);
+        self.activity.push(0.0);
+        self.polarity.push(true);
+        self.seen.push(false);
+        self.heap.indices.push(None);
+        self.heap.insert(v, &self.activity);
+        v as Lit + 1
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn enqueue(&mut self, c: usize, reason: Option<usize>) {
+        let v = c >> 1;
+        self.assigns[v] = if c & 1 == 0 { 1 } else { -1 };
+        self.level[v] = self.decision_level();
+        self.reason[v] = reason;
+        self.trail.push(c);
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let start = self.trail_lim[level];
+        for k in (start..self.trail.len()).rev() {
+            let v = self.trail[k] >> 1;
+            self.polarity[v] = self.trail[k] & 1 == 1;
+            self.assigns[v] = 0;
+            self.reason[v] = None;
+            self.heap.insert(v, &self.activity);
+        }
+        self.trail.truncate(start);
+        self.trail_lim.truncate(level);
+        self.qhead = start;
+    }
+
+    fn attach(&mut self, clause: Vec<usize>) -> usize {
+        let cref = self.clauses.len();
+        self.watches[clause[0]].push(cref);
+        self.watches[clause[1]].push(cref);
+        self.clauses.push(clause);
+        cref
+    }
+
+    // 節を追加する。矛盾が確定したらfalse
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        if !self.ok {
+            return false;
+        }
+        self.backtrack(0);
+        let mut clause: Vec<usize> = Vec::new();
+        for lit in lits {
+            let c = code(*lit);
+            match value(&self.assigns, c) {
+                1 => return true,
+                -1 => continue,
+                _ => {}
+            }
+            if clause.contains(&(c ^ 1)) {
+                return true;
+            }
+            if !clause.contains(&c) {
+                clause.push(c);
+            }
+        }
+        match clause.len() {
+            0 => self.ok = false,
+            1 => {
+                self.enqueue(clause[0], None);
+                if self.propagate().is_some() {
+                    self.ok = false;
+                }
+            }
+            _ => {
+                self.attach(clause);
+            }
+        }
+        self.ok
+    }
+
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let false_lit = self.trail[self.qhead] ^ 1;
+            self.qhead += 1;
+            let mut ws = std::mem::take(&mut self.watches[false_lit]);
+            let mut i = 0;
+            let mut j = 0;
+            let mut conflict = None;
+            while i < ws.len() {
+                let cref = ws[i];
+                i += 1;
+                let clause = &mut self.clauses[cref];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if value(&self.assigns, clause[0]) == 1 {
+                    ws[j] = cref;
+                    j += 1;
+                    continue;
+                }
+                // 別の見張りを探す
+                if let Some(k) = (2..clause.len()).find(|k| value(&self.assigns, clause[*k]) != -1) {
+                    clause.swap(1, k);
+                    let watch = clause[1];
+                    self.watches[watch].push(cref);
+                    continue;
+                }
+                ws[j] = cref;
+                j += 1;
+                let first = clause[0];
+                if value(&self.assigns, first) == -1 {
+                    conflict = Some(cref);
+                    while i < ws.len() {
+                        ws[j] = ws[i];
+                        i += 1;
+                        j += 1;
+                    }
+                } else {
+                    self.enqueue(first, Some(cref));
+                }
+            }
+            ws.truncate(j);
+            self.watches[false_lit] = ws;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    fn bump(&mut self, v: usize) {
+        self.activity[v] += self.var_inc;
+        if self.activity[v] > 1e100 {
+            for a in self.activity.iter_mut() {
+                *a *= 1e-100;
+            }
+            self.var_inc *= 1e-100;
+        }
+        if let Some(k) = self.heap.indices[v] {
+            self.heap.up(k, &self.activity);
+        }
+    }
+
+    // 1-UIPで学習節を作る
+    fn analyze(&mut self, mut conflict: usize) -> (Vec<usize>, usize) {
+        let mut learnt = vec![0];
+        let mut path = 0;
+        let mut p: Option<usize> = None;
+        let mut index = self.trail.len();
+        loop {
+            let clause = self.clauses[conflict].clone();
+            let start = if p.is_none() { 0 } else { 1 };
+            for &q in clause[start..].iter() {
+                let v = q >> 1;
+                if !self.seen[v] && self.level[v] > 0 {
+                    self.bump(v);
+                    self.seen[v] = true;
+                    if self.level[v] >= self.decision_level() {
+                        path += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index] >> 1] {
+                    break;
+                }
+            }
+            let next = self.trail[index];
+            self.seen[next >> 1] = false;
+            path -= 1;
+            p = Some(next);
+            if path == 0 {
+                break;
+            }
+            conflict = self.reason[next >> 1].unwrap();
+        }
+        learnt[0] = p.unwrap() ^ 1;
+        for q in learnt.iter() {
+            self.seen[q >> 1] = false;
+        }
+
+        let mut back = 0;
+        if learnt.len() > 1 {
+            let mut max = 1;
+            for k in 2..learnt.len() {
+                if self.level[learnt[k] >> 1] > self.level[learnt[max] >> 1] {
+                    max = k;
+                }
+            }
+            learnt.swap(1, max);
+            back = self.level[learnt[1] >> 1];
+        }
+        (learnt, back)
+    }
+
+    // 仮定のもとで解く。充足可能ならtrueで、modelが読める
+    pub fn solve(&mut self, assumptions: &[Lit]) -> bool {
+        if !self.ok {
+            return false;
+        }
+        self.backtrack(0);
+        if self.propagate().is_some() {
+            self.ok = false;
+            return false;
+        }
+        let assumptions: Vec<usize> = assumptions.iter().map(|lit| code(*lit)).collect();
+        let mut restarts = 0;
+        let mut conflicts_left = luby(restarts) * 100;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.ok = false;
+                    return false;
+                }
+                let (learnt, back) = self.analyze(conflict);
+                self.backtrack(back);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let first = learnt[0];
+                    let cref = self.attach(learnt);
+                    self.enqueue(first, Some(cref));
+                }
+                self.var_inc /= 0.95;
+                conflicts_left = conflicts_left.saturating_sub(1);
+                continue;
+            }
+
+            if conflicts_left == 0 {
+                restarts += 1;
+                conflicts_left = luby(restarts) * 100;
+                self.backtrack(0);
+                continue;
+            }
+
+            let next = if self.decision_level() < assumptions.len() {
+                let p = assumptions[self.decision_level()];
+                match value(&self.assigns, p) {
+                    1 => {
+                        self.trail_lim.push(self.trail.len());
+                        continue;
+                    }
+                    -1 => {
+                        self.backtrack(0);
+                        return false;
+                    }
+                    _ => p,
+                }
+            } else {
+                let mut next = None;
+                while let Some(v) = self.heap.pop(&self.activity) {
+                    if self.assigns[v] == 0 {
+                        next = Some(v * 2 + self.polarity[v] as usize);
+                        break;
+                    }
+                }
+                match next {
+                    Some(p) => p,
+                    None => {
+                        self.model = self.assigns.iter().map(|a| *a == 1).collect();
+                        self.backtrack(0);
+                        return true;
+                    }
+                }
+            };
+            self.decisions += 1;
+            self.trail_lim.push(self.trail.len());
+            self.enqueue(next, None);
+        }
+    }
+
+    // 直前に見つかった解での変数の値
+    pub fn model_value(&self, lit: Lit) -> bool {
+        let v = self.model[lit.unsigned_abs() as usize - 1];
+        if lit > 0 { v } else { !v }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check, create_board, create_priority_queue, solve, solve_with, BackendEnum, CheckResultEnum};
+    use crate::diff::diff;
+
+    #[test]
+    fn luby_sequence() {
+        let seq: Vec<u64> = (0..15).map(luby).collect();
+        assert_eq!(seq, vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn pigeonhole_is_unsatisfiable() {
+        // 3羽の鳩を2つの巣に入れる
+        let mut solver = Solver::new();
+        let p: Vec<Vec<Lit>> = (0..3).map(|_| (0..2).map(|_| solver.new_var()).collect()).collect();
+        for row in p.iter() {
+            solver.add_clause(row);
+        }
+        for a in 0..3 {
+            for b in a + 1..3 {
+                for (x, y) in p[a].iter().zip(p[b].iter()) {
+                    solver.add_clause(&[-x, -y]);
+                }
+            }
+        }
+        assert!(!solver.solve(&[]));
+    }
+
+    #[test]
+    fn assumptions_do_not_stick() {
+        let mut solver = Solver::new();
+        let (a, b) = (solver.new_var(), solver.new_var());
+        solver.add_clause(&[a, b]);
+        assert!(solver.solve(&[-a]));
+        assert!(!solver.model_value(a) && solver.model_value(b));
+        assert!(!solver.solve(&[-a, -b]));
+        assert!(solver.solve(&[]));
+        assert!(solver.model_value(a) || solver.model_value(b));
+    }
+
+    // main.rsの問題。SATの答えがほかの解き方の答えと同じになる
+    #[test]
+    fn agrees_with_backtracking() {
+        for problem in ["2/2/d", "3/3/40", "2/3/41", "2/5/j", "5/5/g22q"] {
+            let board = create_board(problem);
+            let (_, expected) = solve(&board, &create_priority_queue(&board));
+            let (result, answer) = solve_with(&board, BackendEnum::Sat);
+            assert!(matches!(result, CheckResultEnum::Complete), "{}", problem);
+            assert!(diff(&expected.unwrap(), &answer.unwrap()).unwrap().is_empty(), "{}", problem);
+        }
+    }
+
+    // 10x10はバックトラックでは時間がかかりすぎるので、推論しながらの探索と比べる
+    #[test]
+    fn agrees_with_search_on_large_boards() {
+        for problem in ["10/10/202022l40i4141h40f122242l31i2131h30b42c101210c41i", "10/10/23l24zg21c42n13b11l42m14c"] {
+            let board = create_board(problem);
+            let (_, expected) = solve_with(&board, BackendEnum::Search);
+            let (_, answer) = solve_with(&board, BackendEnum::Sat);
+            let answer = answer.unwrap();
+            assert!(matches!(check(&answer), CheckResultEnum::Complete), "{}", problem);
+            assert!(diff(&expected.unwrap(), &answer).unwrap().is_empty(), "{}", problem);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{clear_board, Board, Cell, DirectionEnum, WallEnum};
+use crate::cnf::{degree, exactly, loop_cuts};
+use crate::grid::{CellStateEnum, EdgeEnum, EdgeStateEnum, FactEnum, Grid};
+use crate::sat::{Lit, Solver};
+
+pub enum UniquenessEnum {
+    Unique(Board),
+    Multiple(Board, Board),
+    NoAnswer,
+}
+
+// ヒントを一つずつ書き換えながら解き直すためのセッション
+// ヒントに依存する節だけを選択変数つきで入れておき、書き換えたら古い選択変数を偽にする
+// それ以外の節と、そこから学習した節はそのまま使い回す
+pub struct EditSession {
+    solver: Solver,
+    height: usize,
+    width: usize,
+    shaded: Vec<Lit>,
+    on_loop: Vec<Lit>,
+    blank: Grid,
+    edges: Vec<(EdgeEnum, Lit)>,
+    clues: Vec<Option<(DirectionEnum, i64)>>,
+    selectors: Vec<Lit>,
+    pub solves: usize,
+}
+
+impl EditSession {
+    pub fn new(board: &Board) -> EditSession {
+        let height = board.0.len();
+        let width = if height == 0 { 0 } else { board.0[0].len() };
+        let blank = Grid::from_board(&Board(vec![vec![Cell::Unknown; width]; height])).unwrap();
+        let mut solver = Solver::new();
+        let shaded: Vec<Lit> = (0..height * width).map(|_| solver.new_var()).collect();
+        let on_loop: Vec<Lit> = (0..height * width).map(|_| solver.new_var()).collect();
+        let edges: Vec<(EdgeEnum, Lit)> = blank.all_edges().into_iter().map(|e| (e, solver.new_var())).collect();
+
+        // ヒントによらない節
+        for i in 0..height {
+            for j in 0..width {
+                let (s, u) = (shaded[i * width + j], on_loop[i * width + j]);
+                solver.add_clause(&[-s, -u]);
+                if i + 1 < height {
+                    solver.add_clause(&[-s, -shaded[(i + 1) * width + j]]);
+                }
+                if j + 1 < width {
+                    solver.add_clause(&[-s, -shaded[i * width + j + 1]]);
+                }
+                // ループ上のマスは線が2本、それ以外は0本
+                let lines: Vec<Lit> = blank.edges_around(i, j).iter().filter_map(|e| edges.iter().find(|(f, _)| f == e)).map(|(_, v)| *v).collect();
+                for clause in degree(&lines, u) {
+                    solver.add_clause(&clause);
+                }
+            }
+        }
+        solver.add_clause(&on_loop);
+
+        let mut session = EditSession {
+            solver,
+            height,
+            width,
+            shaded,
+            on_loop,
+            blank,
+            edges,
+            clues: vec![None; height * width],
+            selectors: vec![0; height * width],
+            solves: 0,
+        };
+        let puzzle = clear_board(board);
+        for i in 0..height {
+            for j in 0..width {
+                let clue = match &puzzle.0[i][j] {
+                    Cell::Wall(WallEnum::Hint(dir, num)) => Some((dir.clone(), *num)),
+                    _ => None,
+                };
+                session.set_clue(i, j, clue);
+            }
+        }
+        session
+    }
+
+    pub fn board(&self) -> Board {
+        Board((0..self.height).map(|i| (0..self.width).map(|j| match &self.clues[i * self.width + j] {
+            Some((dir, num)) => Cell::Wall(WallEnum::Hint(dir.clone(), *num)),
+            None => Cell::Unknown,
+        }).collect()).collect())
+    }
+
+    pub fn clue(&self, i: usize, j: usize) -> Option<&(DirectionEnum, i64)> {
+        self.clues[i * self.width + j].as_ref()
+    }
+
+    // ヒントを置き換える。Noneならヒントのないマスにする
+    pub fn set_clue(&mut self, i: usize, j: usize, clue: Option<(DirectionEnum, i64)>) {
+        let index = i * self.width + j;
+        let old = self.selectors[index];
+        if old != 0 {
+            self.solver.add_clause(&[-old]);
+        }
+        let a = self.solver.new_var();
+        self.selectors[index] = a;
+        let (s, u) = (self.shaded[index], self.on_loop[index]);
+        match &clue {
+            None => {
+                self.solver.add_clause(&[-a, s, u]);
+            }
+            Some((dir, num)) => {
+                self.solver.add_clause(&[-a, -s]);
+                self.solver.add_clause(&[-a, -u]);
+                if *dir != DirectionEnum::None && *num >= 0 {
+                    let xs: Vec<Lit> = self.blank.ray(i, j, dir).iter().map(|(y, x)| self.shaded[y * self.width + x]).collect();
+                    let solver = &mut self.solver;
+                    let clauses = exactly(&xs, *num as usize, &mut || solver.new_var());
+                    for mut clause in clauses {
+                        clause.push(-a);
+                        self.solver.add_clause(&clause);
+                    }
+                }
+            }
+        }
+        self.clues[index] = clue;
+    }
+
+    fn decode(&self) -> Option<Grid> {
+        let mut grid = Grid::from_board(&self.board()).ok()?;
+        for i in 0..self.height {
+            for j in 0..self.width {
+                if grid.cell(i, j) == CellStateEnum::Clue {
+                    continue;
+                }
+                let s = self.solver.model_value(self.shaded[i * self.width + j]);
+                grid.apply(if s { FactEnum::Shaded(i, j) } else { FactEnum::Unshaded(i, j) }).ok()?;
+            }
+        }
+        for (e, v) in self.edges.iter() {
+            let line = self.solver.model_value(*v);
+            grid.apply(if line { FactEnum::Line(*e) } else { FactEnum::Cross(*e) }).ok()?;
+        }
+        Some(grid)
+    }
+
+    // ループが一つになるまで、分かれたループを外と繋ぐ節を足して解き直す
+    fn solve(&mut self, extra: Option<Lit>) -> Option<Grid> {
+        let mut assumptions = self.selectors.clone();
+        assumptions.extend(extra);
+        let width = self.width;
+        let edges: Vec<(usize, usize, Lit)> = self.edges.iter().map(|(e, v)| {
+            let (a, b) = e.cells();
+            (a.0 * width + a.1, b.0 * width + b.1, *v)
+        }).collect();
+        loop {
+            self.solves += 1;
+            if !self.solver.solve(&assumptions) {
+                return None;
+            }
+            let grid = self.decode()?;
+            let cuts = loop_cuts(self.height * width, &edges, |k| Some(self.on_loop[k]), |lit| self.solver.model_value(lit));
+            if cuts.is_empty() {
+                return Some(grid);
+            }
+            for cut in cuts {
+                self.solver.add_clause(&cut);
+            }
+        }
+    }
+
+    // 今のヒントで解が一つだけか
+    pub fn check(&mut self) -> UniquenessEnum {
+        let first = match self.solve(None) {
+            Some(grid) => grid,
+            None => return UniquenessEnum::NoAnswer,
+        };
+        // 一つ目の解を禁止する節は、この確認の間だけ有効にする
+        let t = self.solver.new_var();
+        let mut clause = vec![-t];
+        for index in 0..self.height * self.width {
+            let (i, j) = (index / self.width, index % self.width);
+            if first.cell(i, j) != CellStateEnum::Clue {
+                let s = self.shaded[index];
+                clause.push(if first.cell(i, j) == CellStateEnum::Shaded { -s } else { s });
+            }
+        }
+        for (e, v) in self.edges.iter() {
+            clause.push(if first.edge(*e) == EdgeStateEnum::Line { -v } else { *v });
+        }
+        self.solver.add_clause(&clause);
+        let second = self.solve(Some(t));
+        self.solver.add_clause(&[-t]);
+        match second {
+            Some(grid) => UniquenessEnum::Multiple(first.to_board(), grid.to_board()),
+            None => UniquenessEnum::Unique(first.to_board()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::sat_solutions;
+    use crate::diff::diff;
+    use crate::{check, create_board, CheckResultEnum};
+
+    // 作り直したソルバの答えと同じか
+    fn agrees(session: &mut EditSession) -> bool {
+        let expected = sat_solutions(&session.board(), 2);
+        match session.check() {
+            UniquenessEnum::Unique(solution) => expected.len() == 1 && diff(&solution, &expected[0]).unwrap().is_empty(),
+            UniquenessEnum::Multiple(first, second) => {
+                expected.len() == 2
+                    && matches!(check(&first), CheckResultEnum::Complete)
+                    && matches!(check(&second), CheckResultEnum::Complete)
+                    && !diff(&first, &second).unwrap().is_empty()
+            }
+            UniquenessEnum::NoAnswer => expected.is_empty(),
+        }
+    }
+
+    #[test]
+    fn edits_match_fresh_solves() {
+        let mut session = EditSession::new(&create_board("5/5/g22q"));
+        assert!(matches!(session.check(), UniquenessEnum::Unique(_)));
+        assert!(agrees(&mut session));
+        session.set_clue(1, 2, None);
+        assert!(matches!(session.check(), UniquenessEnum::Multiple(..)));
+        assert!(agrees(&mut session));
+        session.set_clue(1, 2, Some((DirectionEnum::Down, 3)));
+        assert!(matches!(session.check(), UniquenessEnum::NoAnswer));
+        assert!(agrees(&mut session));
+        session.set_clue(1, 2, Some((DirectionEnum::Down, 2)));
+        assert!(matches!(session.check(), UniquenessEnum::Unique(_)));
+        assert!(agrees(&mut session));
+        assert_eq!(session.clue(1, 2), Some(&(DirectionEnum::Down, 2)));
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write;
+use crate::Board;
+use crate::render::{layout, RenderOptions, Rgb, Scene, ShapeEnum};
+
+fn hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.0, color.1, color.2)
+}
+
+fn points(points: &[(f64, f64)]) -> String {
+    points.iter().map(|(x, y)| format!("{:.2},{:.2}", x, y)).collect::<Vec<_>>().join(" ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// 図形の列をsvg要素にする。xmlの宣言を付けないので、htmlにそのまま埋め込める
+pub fn scene_to_svg(scene: &Scene) -> String {
+    let mut out = String::new();
+    writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.2} {:.2}\">", scene.width, scene.height, scene.width, scene.height).unwrap();
+    writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>", hex(scene.background)).unwrap();
+    for shape in scene.shapes.iter() {
+        match shape {
+            ShapeEnum::Rect { x, y, w, h, color, opacity } => {
+                writeln!(out, "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" fill-opacity=\"{}\"/>", x, y, w, h, hex(*color), opacity).unwrap();
+            }
+            ShapeEnum::Line { x1, y1, x2, y2, width, color } => {
+                writeln!(out, "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-width=\"{:.2}\" stroke-linecap=\"square\"/>", x1, y1, x2, y2, hex(*color), width).unwrap();
+            }
+            ShapeEnum::Polyline { points: p, closed, width, color, opacity } => {
+                // 閉じたループはpathにして、継ぎ目も角と同じく丸める
+                let mut d = String::new();
+                for (k, (x, y)) in p.iter().enumerate() {
+                    write!(d, "{}{:.2},{:.2} ", if k == 0 { "M" } else { "L" }, x, y).unwrap();
+                }
+                if *closed {
+                    d.push('Z');
+                }
+                writeln!(out, "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-opacity=\"{}\" stroke-width=\"{:.2}\" stroke-linejoin=\"round\" stroke-linecap=\"round\"/>", d.trim_end(), hex(*color), opacity, width).unwrap();
+            }
+            ShapeEnum::Polygon { points: p, color } => {
+                writeln!(out, "<polygon points=\"{}\" fill=\"{}\"/>", points(p), hex(*color)).unwrap();
+            }
+            ShapeEnum::Circle { x, y, r, color, opacity } => {
+                writeln!(out, "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\" fill-opacity=\"{}\"/>", x, y, r, hex(*color), opacity).unwrap();
+            }
+            ShapeEnum::Text { x, y, size, text, color } => {
+                writeln!(out, "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{:.2}\" font-family=\"sans-serif\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>", x, y, size, hex(*color), escape(text)).unwrap();
+            }
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+pub fn to_svg(board: &Board, options: &RenderOptions) -> String {
+    scene_to_svg(&layout(board, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::sat_solutions;
+    use crate::create_board;
+    use crate::render::ModeEnum;
+
+    // 開いた要素はsvgだけで、ほかは一行ずつ閉じている
+    fn well_formed(svg: &str) -> bool {
+        let lines: Vec<&str> = svg.lines().collect();
+        lines.first().map(|line| line.starts_with("<svg ")).unwrap_or(false)
+            && lines.last() == Some(&"</svg>")
+            && lines[1..lines.len() - 1].iter().all(|line| line.starts_with('<') && (line.ends_with("/>") || line.ends_with("</text>")))
+    }
+
+    #[test]
+    fn puzzle_and_solution() {
+        let board = create_board("10/10/23l24zg21c42n13b11l42m14c");
+        let options = RenderOptions { mode: ModeEnum::Puzzle, ..RenderOptions::default() };
+        let puzzle = to_svg(&board, &options);
+        assert!(well_formed(&puzzle));
+        assert_eq!(puzzle.matches("<text ").count(), 8);
+        // 外枠だけ
+        assert_eq!(puzzle.matches("<path ").count(), 1);
+
+        let solution = to_svg(&sat_solutions(&board, 1)[0], &RenderOptions::default());
+        assert!(well_formed(&solution));
+        assert_eq!(solution.matches("<text ").count(), 8);
+        // 外枠と、閉じたループ
+        assert_eq!(solution.matches("<path ").count(), 2);
+        assert_eq!(solution.matches("Z\" fill=\"none\"").count(), 2);
+    }
+}
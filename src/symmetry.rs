@@ -0,0 +1,99 @@
+// ヒントのマスの配置の対称性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymmetryEnum {
+    None,
+    Rotational180,
+    HorizontalMirror, // 水平な軸で上下に折り返す
+    VerticalMirror,   // 垂直な軸で左右に折り返す
+    Diagonal,         // 左上から右下への対角線で折り返す
+    Rotational90,
+}
+
+impl SymmetryEnum {
+    // 対角線と90度回転は正方形の盤面でしか使えない
+    pub fn fits(&self, height: usize, width: usize) -> bool {
+        match self {
+            SymmetryEnum::Diagonal | SymmetryEnum::Rotational90 => height == width,
+            _ => true,
+        }
+    }
+
+    fn map(&self, i: usize, j: usize, height: usize, width: usize) -> (usize, usize) {
+        match self {
+            SymmetryEnum::None => (i, j),
+            SymmetryEnum::Rotational180 => (height - 1 - i, width - 1 - j),
+            SymmetryEnum::HorizontalMirror => (height - 1 - i, j),
+            SymmetryEnum::VerticalMirror => (i, width - 1 - j),
+            SymmetryEnum::Diagonal => (j, i),
+            SymmetryEnum::Rotational90 => (j, width - 1 - i),
+        }
+    }
+
+    // 一緒に置いたり外したりするマス。自分自身を含む
+    pub fn orbit(&self, i: usize, j: usize, height: usize, width: usize) -> Vec<(usize, usize)> {
+        let mut result = vec![(i, j)];
+        let mut now = self.map(i, j, height, width);
+        while now != (i, j) {
+            result.push(now);
+            now = self.map(now.0, now.1, height, width);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_symmetric;
+    use crate::{Cell, WallEnum};
+
+    fn sorted(mut cells: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn orbits() {
+        assert_eq!(SymmetryEnum::None.orbit(1, 2, 4, 6), vec![(1, 2)]);
+        assert_eq!(SymmetryEnum::Rotational180.orbit(1, 2, 4, 6), vec![(1, 2), (2, 3)]);
+        assert_eq!(SymmetryEnum::HorizontalMirror.orbit(1, 2, 4, 6), vec![(1, 2), (2, 2)]);
+        assert_eq!(SymmetryEnum::VerticalMirror.orbit(1, 2, 4, 6), vec![(1, 2), (1, 3)]);
+        assert_eq!(SymmetryEnum::Diagonal.orbit(1, 3, 4, 4), vec![(1, 3), (3, 1)]);
+        assert_eq!(sorted(SymmetryEnum::Rotational90.orbit(0, 1, 4, 4)), vec![(0, 1), (1, 3), (2, 0), (3, 2)]);
+        // 中心や軸の上のマスは自分だけ
+        assert_eq!(SymmetryEnum::Rotational180.orbit(2, 2, 5, 5), vec![(2, 2)]);
+        assert_eq!(SymmetryEnum::Rotational90.orbit(2, 2, 5, 5), vec![(2, 2)]);
+        assert_eq!(SymmetryEnum::Diagonal.orbit(1, 1, 4, 4), vec![(1, 1)]);
+        assert_eq!(SymmetryEnum::VerticalMirror.orbit(0, 2, 3, 5), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn square_only_symmetries() {
+        for symmetry in [SymmetryEnum::Diagonal, SymmetryEnum::Rotational90] {
+            assert!(symmetry.fits(5, 5));
+            assert!(!symmetry.fits(4, 6));
+            assert!(generate_symmetric(6, 4, 0, symmetry).is_none());
+        }
+        for symmetry in [SymmetryEnum::None, SymmetryEnum::Rotational180, SymmetryEnum::HorizontalMirror, SymmetryEnum::VerticalMirror] {
+            assert!(symmetry.fits(4, 6));
+        }
+    }
+
+    // 生成した問題のヒントの配置は、対称な位置を含めて閉じている
+    #[test]
+    fn generated_clues_are_closed_under_orbits() {
+        for symmetry in [SymmetryEnum::Rotational180, SymmetryEnum::HorizontalMirror, SymmetryEnum::VerticalMirror, SymmetryEnum::Diagonal, SymmetryEnum::Rotational90] {
+            let generated = generate_symmetric(6, 6, 1, symmetry).unwrap();
+            let board = &generated.board;
+            for i in 0..6 {
+                for j in 0..6 {
+                    if let Cell::Wall(WallEnum::Hint(..)) = board.0[i][j] {
+                        for (y, x) in symmetry.orbit(i, j, 6, 6) {
+                            assert!(matches!(board.0[y][x], Cell::Wall(WallEnum::Hint(..))), "{:?}: ({}, {}) without ({}, {})", symmetry, i, j, y, x);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::{Duration, Instant};
+use crate::{encode_board, Board, DirectionEnum};
+use crate::cnf::sat_solutions;
+use crate::generator::{generate_symmetric, Answer, Generated};
+use crate::grid::DIRECTIONS;
+use crate::minimize::{minimize_symmetric, OrderEnum, ReductionEnum};
+use crate::random::Rng;
+use crate::rating::{rate, DifficultyEnum, Rating};
+use crate::symmetry::SymmetryEnum;
+
+// どこまで探すか。Attemptsなら実行環境によらず同じ結果になる
+// Timeは生成、最小化、難易度の判定の合間に確かめる。一回の生成や判定は途中で止めないので、少し超えることがある
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetEnum {
+    Time(Duration),
+    Attempts(usize),
+}
+
+pub struct Targeted {
+    pub generated: Generated,
+    pub rating: Rating,
+    pub hit: bool, // 目標の難易度に入ったか
+    pub attempts: usize,
+}
+
+fn distance(score: u32, band: DifficultyEnum) -> u32 {
+    let (low, high) = band.range();
+    if score < low {
+        low - score
+    } else {
+        score.saturating_sub(high)
+    }
+}
+
+// ヒントを足して易しくする。向きのないヒントに向きを付けるか、黒マスの組をヒントに置き換える
+fn ease(solution: &Board, symmetry: SymmetryEnum, rng: &mut Rng) -> Option<Answer> {
+    let answer = Answer::from_solution(solution);
+    let (height, width) = (answer.height, answer.width);
+    let mut options: Vec<Vec<(usize, usize)>> = Vec::new();
+    for k in 0..height * width {
+        let (i, j) = (k / width, k % width);
+        if answer.clues[k] == Some(DirectionEnum::None) {
+            options.push(vec![(i, j)]);
+        } else if answer.shaded[k] {
+            let orbit = symmetry.orbit(i, j, height, width);
+            if orbit.iter().all(|(y, x)| answer.shaded[y * width + x]) {
+                options.push(orbit);
+            }
+        }
+    }
+    rng.shuffle(&mut options);
+    for cells in options.into_iter().take(8) {
+        let mut next = answer.clone();
+        for (i, j) in cells {
+            let dirs: Vec<_> = DIRECTIONS.iter().filter(|dir| !next.ray(i, j, dir).is_empty()).collect();
+            next.shaded[i * width + j] = false;
+            next.clues[i * width + j] = dirs.get(rng.below(dirs.len().max(1))).map(|dir| (*dir).clone());
+        }
+        if sat_solutions(&next.puzzle(), 2).len() == 1 {
+            return Some(next);
+        }
+    }
+    None
+}
+
+// 目標の難易度に入るまで作り直したり、ヒントを減らしたり足したりする
+// 入らなかったときは一番近かったものを返す
+pub fn generate_to(width: usize, height: usize, seed: u64, band: DifficultyEnum, symmetry: SymmetryEnum, budget: BudgetEnum) -> Option<Targeted> {
+    let start = Instant::now();
+    let mut rng = Rng::new(seed);
+    let mut best: Option<Targeted> = None;
+    let mut attempts = 0;
+    let within = |attempts: usize| match budget {
+        BudgetEnum::Time(limit) => start.elapsed() < limit,
+        BudgetEnum::Attempts(limit) => attempts < limit,
+    };
+
+    while within(attempts) && !best.as_ref().map(|b| b.hit).unwrap_or(false) {
+        attempts += 1;
+        let attempt_seed = rng.next_u64();
+        let raw = match generate_symmetric(width, height, attempt_seed, symmetry) {
+            Some(raw) => raw,
+            None => continue,
+        };
+        let mut candidates = vec![(raw.board.clone(), raw.solution.clone())];
+        if within(attempts) {
+            if let Some(minimized) = minimize_symmetric(&raw.board, OrderEnum::Random(attempt_seed), &[ReductionEnum::Remove, ReductionEnum::Blank], symmetry) {
+                candidates.push((minimized.board, minimized.solution));
+            }
+        }
+
+        for (n, (board, solution)) in candidates.into_iter().enumerate() {
+            // 何も見つかっていなければ、時間切れでも最初の候補は判定する
+            if !within(attempts) && (n > 0 || best.is_some()) {
+                break;
+            }
+            let (mut board, mut solution) = (board, solution);
+            while let Ok(rating) = rate(&board) {
+                let better = best.as_ref().map(|b| distance(rating.score, band) < distance(b.rating.score, band)).unwrap_or(true);
+                let too_hard = rating.label > band;
+                if better {
+                    let pzpr = encode_board(&board);
+                    let hit = rating.label == band;
+                    best = Some(Targeted { generated: Generated { board: board.clone(), solution: solution.clone(), pzpr }, rating, hit, attempts });
+                }
+                if !too_hard || !within(attempts) || best.as_ref().map(|b| b.hit).unwrap_or(false) {
+                    break;
+                }
+                match ease(&solution, symmetry, &mut rng) {
+                    Some(next) => {
+                        board = next.puzzle();
+                        solution = next.solution();
+                    }
+                    None => break,
+                }
+            }
+            if best.as_ref().map(|b| b.hit).unwrap_or(false) {
+                break;
+            }
+        }
+    }
+    if let Some(best) = best.as_mut() {
+        best.attempts = attempts;
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_at_band_edges() {
+        assert_eq!(distance(25, DifficultyEnum::Medium), 0);
+        assert_eq!(distance(44, DifficultyEnum::Medium), 0);
+        assert_eq!(distance(24, DifficultyEnum::Medium), 1);
+        assert_eq!(distance(45, DifficultyEnum::Medium), 1);
+        assert_eq!(distance(0, DifficultyEnum::Easy), 0);
+        assert_eq!(distance(0, DifficultyEnum::Expert), 65);
+        assert_eq!(distance(u32::MAX, DifficultyEnum::Expert), 0);
+    }
+
+    // 回数で区切れば、同じseedから同じ問題になる
+    #[test]
+    fn attempts_budget_is_reproducible() {
+        for band in [DifficultyEnum::Easy, DifficultyEnum::Hard] {
+            let first = generate_to(6, 6, 3, band, SymmetryEnum::None, BudgetEnum::Attempts(3)).unwrap();
+            let second = generate_to(6, 6, 3, band, SymmetryEnum::None, BudgetEnum::Attempts(3)).unwrap();
+            assert_eq!(first.generated.pzpr, second.generated.pzpr);
+            assert_eq!((first.rating.score, first.attempts), (second.rating.score, second.attempts));
+            assert!(first.attempts <= 3);
+            assert_eq!(first.hit, first.rating.label == band);
+            assert_eq!(sat_solutions(&first.generated.board, 2).len(), 1);
+        }
+    }
+}
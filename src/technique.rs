@@ -0,0 +1,599 @@
+use std::fmt;
+use union_find::UnionFind;
+use crate::{Board, CheckResultEnum, CheckResultInvalidEnum, DirectionEnum};
+use crate::grid::{CellStateEnum, EdgeStateEnum, FactEnum, Grid};
+
+// 人間が使う解き方。簡単な順に並んでいる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TechniqueEnum {
+    ShadedNeighbours,
+    LineCompletion,
+    ClueRunSaturation,
+    CornerForcing,
+    DeadEndAvoidance,
+    NoSmallLoops,
+    Parity,
+    TrialAndError, // 仮定して矛盾を探す
+    Guess,         // 仮定したまま進める
+    Backtrack,     // 仮定が失敗したので逆が確定
+}
+
+// 仮定を使わない解き方
+pub const TECHNIQUES: [TechniqueEnum; 7] = [
+    TechniqueEnum::ShadedNeighbours,
+    TechniqueEnum::LineCompletion,
+    TechniqueEnum::ClueRunSaturation,
+    TechniqueEnum::CornerForcing,
+    TechniqueEnum::DeadEndAvoidance,
+    TechniqueEnum::NoSmallLoops,
+    TechniqueEnum::Parity,
+];
+
+impl TechniqueEnum {
+    pub fn description(&self) -> &'static str {
+        match self {
+            TechniqueEnum::ShadedNeighbours => "cells next to a shaded cell are on the loop",
+            TechniqueEnum::LineCompletion => "a cell with a line is on the loop, and a cell with two lines has no other line",
+            TechniqueEnum::ClueRunSaturation => "the clue already has all its shaded cells, or needs every cell it can still get",
+            TechniqueEnum::CornerForcing => "a loop cell with exactly two possible exits uses both",
+            TechniqueEnum::DeadEndAvoidance => "a cell with at most one exit cannot be on the loop, and shading must not leave a loop cell without two exits",
+            TechniqueEnum::NoSmallLoops => "joining the two ends of one line would close a loop that misses other loop cells",
+            TechniqueEnum::Parity => "a closed loop visits as many dark as light cells of the checkerboard",
+            TechniqueEnum::TrialAndError => "assuming the opposite leads to a contradiction",
+            TechniqueEnum::Guess => "no technique applies, so this is a guess",
+            TechniqueEnum::Backtrack => "the guess led to no solution, so the opposite holds",
+        }
+    }
+}
+
+impl fmt::Display for TechniqueEnum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// 1手分の推論
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub technique: TechniqueEnum,
+    pub changes: Vec<FactEnum>,
+    pub reasons: Vec<(usize, usize)>, // 根拠になったマス
+    pub depth: usize, // 仮定の深さ。仮定なしは0
+}
+
+impl Step {
+    // 変化したマス
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        let mut cells: Vec<(usize, usize)> = self.changes.iter().flat_map(|fact| fact.cells()).collect();
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.technique)?;
+        for (k, fact) in self.changes.iter().enumerate() {
+            if k > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", fact)?;
+        }
+        if !self.reasons.is_empty() {
+            write!(f, " (because of")?;
+            for (i, j) in self.reasons.iter() {
+                write!(f, " ({},{})", i, j)?;
+            }
+            write!(f, ")")?;
+        }
+        if self.depth > 0 {
+            write!(f, " [depth {}]", self.depth)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Explanation {
+    pub result: CheckResultEnum,
+    pub steps: Vec<Step>,
+    pub board: Board,
+}
+
+impl Explanation {
+    pub fn used_trial_and_error(&self) -> bool {
+        self.steps.iter().any(|step| step.technique >= TechniqueEnum::TrialAndError)
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut fallback = false;
+        for (k, step) in self.steps.iter().enumerate() {
+            if step.technique >= TechniqueEnum::TrialAndError && !fallback {
+                writeln!(f, "-- techniques ran out, falling back to trial and error --")?;
+                fallback = true;
+            }
+            writeln!(f, "{:>4}. {}", k + 1, step)?;
+        }
+        writeln!(f, "{:?}", self.result)?;
+        write!(f, "{}", self.board)
+    }
+}
+
+fn push_step(steps: &mut Vec<Step>, grid: &Grid, technique: TechniqueEnum, changes: Vec<FactEnum>, reasons: Vec<(usize, usize)>) {
+    let mut new_changes: Vec<FactEnum> = Vec::new();
+    for fact in changes {
+        if grid.holds(fact) != Some(true) && !new_changes.contains(&fact) {
+            new_changes.push(fact);
+        }
+    }
+    if !new_changes.is_empty() {
+        steps.push(Step { technique, changes: new_changes, reasons, depth: 0 });
+    }
+}
+
+// 盤面に今使える推論をすべて探す
+pub fn find(technique: TechniqueEnum, grid: &Grid) -> Vec<Step> {
+    let mut steps = Vec::new();
+    match technique {
+        TechniqueEnum::ShadedNeighbours => {
+            for i in 0..grid.height() {
+                for j in 0..grid.width() {
+                    if grid.cell(i, j) != CellStateEnum::Shaded {
+                        continue;
+                    }
+                    let mut changes: Vec<FactEnum> = grid.neighbours(i, j)
+                        .into_iter()
+                        .filter(|(y, x)| grid.cell(*y, *x) != CellStateEnum::Clue)
+                        .map(|(y, x)| FactEnum::Unshaded(y, x))
+                        .collect();
+                    changes.extend(grid.edges_around(i, j).into_iter().map(FactEnum::Cross));
+                    push_step(&mut steps, grid, technique, changes, vec![(i, j)]);
+                }
+            }
+        }
+        TechniqueEnum::LineCompletion => {
+            for i in 0..grid.height() {
+                for j in 0..grid.width() {
+                    let lines = grid.line_count(i, j);
+                    if lines == 0 {
+                        continue;
+                    }
+                    let mut changes = vec![FactEnum::Unshaded(i, j)];
+                    if lines == 2 {
+                        changes.extend(grid.edges_around(i, j).into_iter()
+                            .filter(|e| grid.edge(*e) != EdgeStateEnum::Line)
+                            .map(FactEnum::Cross));
+                    }
+                    push_step(&mut steps, grid, technique, changes, vec![(i, j)]);
+                }
+            }
+        }
+        TechniqueEnum::ClueRunSaturation => {
+            for i in 0..grid.height() {
+                for j in 0..grid.width() {
+                    let (dir, num) = match grid.clue(i, j) {
+                        Some((dir, num)) if *dir != DirectionEnum::None && *num >= 0 => (dir, *num),
+                        _ => continue,
+                    };
+                    let ray = grid.ray(i, j, dir);
+                    let (shaded, max) = grid.clue_counts(i, j, dir);
+                    let mut reasons = vec![(i, j)];
+                    reasons.extend(ray.iter().filter(|(y, x)| grid.cell(*y, *x) == CellStateEnum::Shaded));
+                    let mut changes = Vec::new();
+                    if shaded == num {
+                        // もう黒マスは置けない
+                        changes.extend(ray.iter()
+                            .filter(|(y, x)| grid.cell(*y, *x) == CellStateEnum::Unknown)
+                            .map(|(y, x)| FactEnum::Unshaded(*y, *x)));
+                    } else if shaded + max == num {
+                        // 奇数の長さの区間は、端から一つおきに黒マスが決まる
+                        let mut run: Vec<(usize, usize)> = Vec::new();
+                        for k in 0..=ray.len() {
+                            if k < ray.len() && grid.can_shade(ray[k].0, ray[k].1) {
+                                run.push(ray[k]);
+                                continue;
+                            }
+                            if run.len() % 2 == 1 {
+                                for (l, (y, x)) in run.iter().enumerate() {
+                                    changes.push(if l % 2 == 0 { FactEnum::Shaded(*y, *x) } else { FactEnum::Unshaded(*y, *x) });
+                                }
+                            }
+                            run.clear();
+                        }
+                    }
+                    push_step(&mut steps, grid, technique, changes, reasons);
+                }
+            }
+        }
+        TechniqueEnum::CornerForcing => {
+            for i in 0..grid.height() {
+                for j in 0..grid.width() {
+                    if grid.cell(i, j) != CellStateEnum::Unshaded || grid.open_count(i, j) != 2 || grid.line_count(i, j) == 2 {
+                        continue;
+                    }
+                    let changes = grid.edges_around(i, j).into_iter()
+                        .filter(|e| grid.edge(*e) != EdgeStateEnum::Cross)
+                        .map(FactEnum::Line)
+                        .collect();
+                    let mut reasons = vec![(i, j)];
+                    reasons.extend(grid.edges_around(i, j).into_iter()
+                        .filter(|e| grid.edge(*e) == EdgeStateEnum::Cross)
+                        .map(|e| {
+                            let (a, b) = e.cells();
+                            if a == (i, j) { b } else { a }
+                        }));
+                    push_step(&mut steps, grid, technique, changes, reasons);
+                }
+            }
+        }
+        TechniqueEnum::DeadEndAvoidance => {
+            for i in 0..grid.height() {
+                for j in 0..grid.width() {
+                    if grid.cell(i, j) != CellStateEnum::Unknown {
+                        continue;
+                    }
+                    if grid.open_count(i, j) <= 1 {
+                        let mut reasons = vec![(i, j)];
+                        reasons.extend(grid.neighbours(i, j));
+                        push_step(&mut steps, grid, technique, vec![FactEnum::Shaded(i, j)], reasons);
+                        continue;
+                    }
+                    // 黒マスにすると隣の線が行き止まりになる
+                    for (y, x) in grid.neighbours(i, j) {
+                        if grid.cell(y, x) != CellStateEnum::Unshaded {
+                            continue;
+                        }
+                        let between = grid.edges_around(y, x).into_iter().filter(|e| {
+                            let (a, b) = e.cells();
+                            (a == (i, j) || b == (i, j)) && grid.edge(*e) != EdgeStateEnum::Cross
+                        }).count();
+                        if grid.open_count(y, x) - between < 2 {
+                            push_step(&mut steps, grid, technique, vec![FactEnum::Unshaded(i, j)], vec![(i, j), (y, x)]);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        TechniqueEnum::NoSmallLoops => {
+            let mut uf = grid.line_components();
+            let mut loop_cells = Vec::new();
+            for i in 0..grid.height() {
+                for j in 0..grid.width() {
+                    if grid.cell(i, j) == CellStateEnum::Unshaded || grid.line_count(i, j) > 0 {
+                        loop_cells.push(i * grid.width() + j);
+                    }
+                }
+            }
+            for e in grid.all_edges() {
+                if grid.edge(e) != EdgeStateEnum::Unknown {
+                    continue;
+                }
+                let (a, b) = e.cells();
+                if grid.line_count(a.0, a.1) != 1 || grid.line_count(b.0, b.1) != 1 {
+                    continue;
+                }
+                let root = uf.find(a.0 * grid.width() + a.1);
+                if root != uf.find(b.0 * grid.width() + b.1) {
+                    continue;
+                }
+                // 閉じたループの外に線が通るマスが残るなら閉じてはいけない
+                if loop_cells.iter().any(|c| uf.find(*c) != root) {
+                    push_step(&mut steps, grid, technique, vec![FactEnum::Cross(e)], vec![a, b]);
+                }
+            }
+        }
+        TechniqueEnum::Parity => {
+            let [(black, black_unknown), (white, white_unknown)] = grid.parity_counts();
+            let diff = white - black;
+            // 黒(i + j が偶数)の未確定マスがすべて線になるか、すべて黒マスになるか
+            let black_on_loop = if black_unknown + white_unknown == 0 {
+                return steps;
+            } else if diff == black_unknown {
+                true
+            } else if diff == -white_unknown {
+                false
+            } else {
+                return steps;
+            };
+            let mut changes = Vec::new();
+            for i in 0..grid.height() {
+                for j in 0..grid.width() {
+                    if grid.cell(i, j) == CellStateEnum::Unknown {
+                        changes.push(if ((i + j) % 2 == 0) == black_on_loop { FactEnum::Unshaded(i, j) } else { FactEnum::Shaded(i, j) });
+                    }
+                }
+            }
+            push_step(&mut steps, grid, technique, changes, vec![]);
+        }
+        TechniqueEnum::TrialAndError | TechniqueEnum::Guess | TechniqueEnum::Backtrack => {}
+    }
+    steps
+}
+
+// 仮定を使わない推論で確定できるところまで進める
+pub fn propagate(grid: &mut Grid) -> Result<(), CheckResultInvalidEnum> {
+    loop {
+        grid.validate()?;
+        let mut changed = false;
+        for technique in TECHNIQUES {
+            for step in find(technique, grid) {
+                for fact in step.changes {
+                    changed |= grid.apply(fact)?;
+                }
+            }
+        }
+        if !changed {
+            return Ok(());
+        }
+    }
+}
+
+// depthまでの仮定を使って確定できるところまで進める
+fn deduce(grid: &mut Grid, depth: usize) -> Result<(), CheckResultInvalidEnum> {
+    loop {
+        propagate(grid)?;
+        if depth == 0 || grid.is_complete() {
+            return Ok(());
+        }
+        match trial(grid, depth)? {
+            Some(step) => {
+                for fact in step.changes {
+                    grid.apply(fact)?;
+                }
+            }
+            None => return Ok(()),
+        }
+    }
+}
+
+// 一つ仮定を置いて矛盾すれば、その逆が確定する
+pub fn trial(grid: &Grid, depth: usize) -> Result<Option<Step>, CheckResultInvalidEnum> {
+    for d in 1..=depth {
+        for fact in grid.undecided() {
+            for assumption in [fact, fact.negate()] {
+                let mut next = grid.clone();
+                let result = next.apply(assumption).and_then(|_| deduce(&mut next, d - 1));
+                if let Err(reason) = result {
+                    let mut reasons = assumption.cells();
+                    if let Some(cell) = invalid_cell(&reason) {
+                        if !reasons.contains(&cell) {
+                            reasons.push(cell);
+                        }
+                    }
+                    return Ok(Some(Step { technique: TechniqueEnum::TrialAndError, changes: vec![assumption.negate()], reasons, depth: d }));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+pub fn invalid_cell(reason: &CheckResultInvalidEnum) -> Option<(usize, usize)> {
+    match *reason {
+        CheckResultInvalidEnum::AdjacentWall(i, j)
+        | CheckResultInvalidEnum::Hint(i, j)
+        | CheckResultInvalidEnum::Line(i, j)
+        | CheckResultInvalidEnum::MultipleLoops(i, j)
+        | CheckResultInvalidEnum::Conflict(i, j) => Some((i as usize, j as usize)),
+        CheckResultInvalidEnum::NoAnswer => None,
+    }
+}
+
+// 仮定を置くときの候補。線の端を伸ばすのを優先する
+pub fn branch(grid: &Grid) -> Option<FactEnum> {
+    for i in 0..grid.height() {
+        for j in 0..grid.width() {
+            if grid.line_count(i, j) != 1 {
+                continue;
+            }
+            if let Some(e) = grid.edges_around(i, j).into_iter().find(|e| grid.edge(*e) == EdgeStateEnum::Unknown) {
+                return Some(FactEnum::Line(e));
+            }
+        }
+    }
+    grid.undecided().into_iter().next()
+}
+
+// 一番簡単な次の一手
+pub fn next_step(grid: &Grid) -> Result<Option<Step>, CheckResultInvalidEnum> {
+    grid.validate()?;
+    for technique in TECHNIQUES {
+        if let Some(step) = find(technique, grid).into_iter().next() {
+            return Ok(Some(step));
+        }
+    }
+    trial(grid, MAX_TRIAL_DEPTH)
+}
+
+pub const MAX_TRIAL_DEPTH: usize = 1;
+
+// 盤面はその場で進める。失敗したときは矛盾した時点の盤面が残る
+fn explain_from(grid: &mut Grid, steps: &mut Vec<Step>, depth: usize) -> Result<(), CheckResultInvalidEnum> {
+    loop {
+        grid.validate()?;
+        if grid.is_complete() {
+            return Ok(());
+        }
+        if let Some(step) = next_step(grid)? {
+            for fact in step.changes.iter() {
+                grid.apply(*fact)?;
+            }
+            steps.push(step);
+            continue;
+        }
+
+        // どの解き方も使えないので仮定を置く
+        let fact = branch(grid).ok_or(CheckResultInvalidEnum::NoAnswer)?;
+        let mut next = grid.clone();
+        let mut sub_steps = vec![Step { technique: TechniqueEnum::Guess, changes: vec![fact], reasons: vec![], depth: depth + 1 }];
+        if next.apply(fact).and_then(|_| explain_from(&mut next, &mut sub_steps, depth + 1)).is_ok() {
+            steps.extend(sub_steps);
+            *grid = next;
+            return Ok(());
+        }
+        grid.apply(fact.negate())?;
+        steps.push(Step { technique: TechniqueEnum::Backtrack, changes: vec![fact.negate()], reasons: fact.cells(), depth: depth + 1 });
+    }
+}
+
+// 解き方の名前付きで一手ずつ解く
+pub fn explain(board: &Board) -> Explanation {
+    let mut grid = match Grid::from_board(board) {
+        Ok(grid) => grid,
+        Err(reason) => return Explanation { result: CheckResultEnum::Invalid(reason), steps: vec![], board: board.clone() },
+    };
+    let mut steps = Vec::new();
+    let result = match explain_from(&mut grid, &mut steps, 0) {
+        Ok(()) => CheckResultEnum::Complete,
+        Err(reason) => CheckResultEnum::Invalid(reason),
+    };
+    Explanation { result, steps, board: grid.to_board() }
+}
+
+// 解をlimit個まで探す
+pub fn search(grid: &Grid, limit: usize, solutions: &mut Vec<Grid>) {
+    let mut grid = grid.clone();
+    if propagate(&mut grid).is_err() {
+        return;
+    }
+    if grid.is_complete() {
+        solutions.push(grid);
+        return;
+    }
+    let fact = match branch(&grid) {
+        Some(fact) => fact,
+        None => return,
+    };
+    for assumption in [fact, fact.negate()] {
+        if solutions.len() >= limit {
+            return;
+        }
+        let mut next = grid.clone();
+        if next.apply(assumption).is_ok() {
+            search(&next, limit, solutions);
+        }
+    }
+}
+
+pub fn solutions(board: &Board, limit: usize) -> Vec<Board> {
+    let mut result = Vec::new();
+    if let Ok(grid) = Grid::from_board(board) {
+        search(&grid, limit, &mut result);
+    }
+    result.iter().map(|grid| grid.to_board()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::sat_solutions;
+    use crate::create_board;
+    use crate::format::parse_text;
+    use crate::grid::EdgeEnum;
+
+    fn from_text(text: &str) -> Grid {
+        Grid::from_board(&parse_text(text).unwrap()).unwrap()
+    }
+
+    fn changes(technique: TechniqueEnum, grid: &Grid) -> Vec<FactEnum> {
+        find(technique, grid).into_iter().inspect(|step| assert_eq!(step.technique, technique)).flat_map(|step| step.changes).collect()
+    }
+
+    #[test]
+    fn shaded_neighbours() {
+        let grid = from_text("??????\n?? W??\n??????\n");
+        assert_eq!(changes(TechniqueEnum::ShadedNeighbours, &grid), vec![
+            FactEnum::Unshaded(0, 1), FactEnum::Unshaded(2, 1), FactEnum::Unshaded(1, 0), FactEnum::Unshaded(1, 2),
+            FactEnum::Cross(EdgeEnum::Down(0, 1)), FactEnum::Cross(EdgeEnum::Down(1, 1)), FactEnum::Cross(EdgeEnum::Right(1, 0)), FactEnum::Cross(EdgeEnum::Right(1, 1)),
+        ]);
+    }
+
+    #[test]
+    fn line_completion() {
+        // (1,2)には上下から線が来ている
+        let grid = from_text("????↓?\n??????\n????↑?\n");
+        assert_eq!(changes(TechniqueEnum::LineCompletion, &grid), vec![FactEnum::Unshaded(1, 2), FactEnum::Cross(EdgeEnum::Right(1, 1))]);
+    }
+
+    #[test]
+    fn clue_run_saturation() {
+        // 3マスに2つの黒マスは、両端に決まる
+        let grid = from_text("→2??????\n");
+        assert_eq!(changes(TechniqueEnum::ClueRunSaturation, &grid), vec![FactEnum::Shaded(0, 1), FactEnum::Unshaded(0, 2), FactEnum::Shaded(0, 3)]);
+        // もう数が足りている
+        let grid = from_text("→1 W????\n");
+        assert_eq!(changes(TechniqueEnum::ClueRunSaturation, &grid), vec![FactEnum::Unshaded(0, 2), FactEnum::Unshaded(0, 3)]);
+    }
+
+    #[test]
+    fn corner_forcing() {
+        let grid = from_text("..????\n??????\n??????\n");
+        assert_eq!(changes(TechniqueEnum::CornerForcing, &grid), vec![FactEnum::Line(EdgeEnum::Down(0, 0)), FactEnum::Line(EdgeEnum::Right(0, 0))]);
+    }
+
+    #[test]
+    fn dead_end_avoidance() {
+        // ヒントの両隣の角は出口が一つしかない
+        let grid = from_text("?? 0??\n??????\n??????\n");
+        assert_eq!(changes(TechniqueEnum::DeadEndAvoidance, &grid), vec![FactEnum::Shaded(0, 0), FactEnum::Shaded(0, 2)]);
+        // 黒マスにすると(0,0)の出口が一つになる
+        let grid = from_text(".. 0??\n??????\n??????\n");
+        assert_eq!(changes(TechniqueEnum::DeadEndAvoidance, &grid), vec![FactEnum::Shaded(0, 2), FactEnum::Unshaded(1, 0)]);
+    }
+
+    #[test]
+    fn no_small_loops() {
+        // U字の線を閉じると(2,2)が残る
+        let grid = from_text("↓→←↓??\n↑?↑???\n????..\n");
+        assert_eq!(changes(TechniqueEnum::NoSmallLoops, &grid), vec![FactEnum::Cross(EdgeEnum::Right(1, 0))]);
+    }
+
+    #[test]
+    fn parity() {
+        // 両方の色に未確定のマスがあれば決まらない
+        let grid = from_text("????\n....\n");
+        assert_eq!(changes(TechniqueEnum::Parity, &grid), vec![]);
+        // 白の2マスが線なので、黒の(0,0)も線になる
+        let grid = from_text("??..\n....\n");
+        assert_eq!(changes(TechniqueEnum::Parity, &grid), vec![FactEnum::Unshaded(0, 0)]);
+    }
+
+    #[test]
+    fn trial_and_error() {
+        // 2x2の角を黒マスにすると、隣のマスの出口が足りない
+        let grid = from_text("????\n????\n");
+        let step = trial(&grid, 1).unwrap().unwrap();
+        assert_eq!(step.technique, TechniqueEnum::TrialAndError);
+        assert_eq!(step.changes, vec![FactEnum::Unshaded(0, 0)]);
+        assert_eq!(step.depth, 1);
+    }
+
+    #[test]
+    fn guess_and_backtrack() {
+        // 解が複数あるので仮定したまま進める
+        let explanation = explain(&create_board("3/3/"));
+        assert!(matches!(explanation.result, CheckResultEnum::Complete));
+        assert!(explanation.steps.iter().any(|step| step.technique == TechniqueEnum::Guess));
+        // 仮定が失敗して逆が確定する
+        let explanation = explain(&create_board("6/6/k11j30m"));
+        assert!(matches!(explanation.result, CheckResultEnum::Complete));
+        let backtrack = explanation.steps.iter().find(|step| step.technique == TechniqueEnum::Backtrack).unwrap();
+        assert_eq!(backtrack.changes.len(), 1);
+        assert!(explanation.used_trial_and_error());
+    }
+
+    #[test]
+    fn explain_matches_sat() {
+        let board = create_board("5/5/g22q");
+        let explanation = explain(&board);
+        assert!(matches!(explanation.result, CheckResultEnum::Complete));
+        assert!(!explanation.steps.iter().any(|step| step.technique >= TechniqueEnum::Guess));
+        let solution = sat_solutions(&board, 2);
+        assert_eq!(solution.len(), 1);
+        assert!(crate::diff::diff(&explanation.board, &solution[0]).unwrap().is_empty());
+        assert_eq!(solutions(&board, 2).len(), 1);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{check, Board, Cell, CheckResultEnum, CheckResultInvalidEnum, DirectionEnum, WallEnum};
+
+const RESET: &str = "\x1b[0m";
+const ARROW: &str = "\x1b[36m";
+const NUMBER: &str = "\x1b[1m";
+const LOOP: &str = "\x1b[32m";
+const SHADED: &str = "\x1b[37m";
+const DOT: &str = "\x1b[2m";
+const VIOLATION: &str = "\x1b[41m";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalOptions {
+    pub unicode: bool, // falseならASCIIだけで描く
+    pub color: bool,
+    pub highlights: Vec<(usize, usize)>, // 目立たせるマス
+    pub check: bool,                     // checkが指摘するマスも目立たせる
+}
+
+impl Default for TerminalOptions {
+    fn default() -> TerminalOptions {
+        TerminalOptions { unicode: true, color: true, highlights: Vec::new(), check: true }
+    }
+}
+
+// checkが指摘するマス
+pub fn violations(board: &Board) -> Vec<(usize, usize)> {
+    let at = |i: i32, j: i32| if i >= 0 && j >= 0 { vec![(i as usize, j as usize)] } else { vec![] };
+    match check(board) {
+        CheckResultEnum::Invalid(reason) => match reason {
+            CheckResultInvalidEnum::AdjacentWall(i, j)
+            | CheckResultInvalidEnum::Hint(i, j)
+            | CheckResultInvalidEnum::Line(i, j)
+            | CheckResultInvalidEnum::MultipleLoops(i, j)
+            | CheckResultInvalidEnum::Conflict(i, j) => at(i, j),
+            CheckResultInvalidEnum::NoAnswer => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+fn arrow(dir: &DirectionEnum, unicode: bool) -> char {
+    match (dir, unicode) {
+        (DirectionEnum::Up, true) => '↑',
+        (DirectionEnum::Down, true) => '↓',
+        (DirectionEnum::Left, true) => '←',
+        (DirectionEnum::Right, true) => '→',
+        (DirectionEnum::Up, false) => '^',
+        (DirectionEnum::Down, false) => 'v',
+        (DirectionEnum::Left, false) => '<',
+        (DirectionEnum::Right, false) => '>',
+        (DirectionEnum::None, _) => ' ',
+    }
+}
+
+// 線の形。右に伸びるなら、次の文字まで横線で繋ぐ
+fn segment(one: &Option<DirectionEnum>, another: &Option<DirectionEnum>, unicode: bool) -> (char, bool) {
+    let has = |dir: DirectionEnum| one.as_ref() == Some(&dir) || another.as_ref() == Some(&dir);
+    let (up, down, left, right) = (has(DirectionEnum::Up), has(DirectionEnum::Down), has(DirectionEnum::Left), has(DirectionEnum::Right));
+    let c = if unicode {
+        match (up, down, left, right) {
+            (false, false, true, true) => '─',
+            (true, true, false, false) => '│',
+            (false, true, false, true) => '┌',
+            (false, true, true, false) => '┐',
+            (true, false, false, true) => '└',
+            (true, false, true, false) => '┘',
+            (true, false, false, false) => '╵',
+            (false, true, false, false) => '╷',
+            (false, false, true, false) => '╴',
+            (false, false, false, true) => '╶',
+            _ => '·',
+        }
+    } else {
+        match (up, down, left, right) {
+            (false, false, true, true) | (false, false, true, false) | (false, false, false, true) => '-',
+            (true, true, false, false) | (true, false, false, false) | (false, true, false, false) => '|',
+            (false, false, false, false) => '.',
+            _ => '+',
+        }
+    };
+    (c, right)
+}
+
+// 罫線や矢印を使って盤面を端末に描く
+pub fn to_terminal(board: &Board, options: &TerminalOptions) -> String {
+    let mut highlights = options.highlights.clone();
+    if options.check {
+        highlights.extend(violations(board));
+    }
+    // 一番長い数字と矢印が入るように、どのマスも同じ幅にする
+    let digits = board.0.iter().flatten().filter_map(|cell| match cell {
+        Cell::Wall(WallEnum::Hint(_, num)) if *num >= 0 => Some(num.to_string().len()),
+        _ => None,
+    }).max().unwrap_or(1);
+    let cell_width = digits + 1;
+    // 色を使えないときは、マスの右に一文字足して目立たせるマスに'!'を書く
+    let mark = !options.color && !highlights.is_empty();
+    let paint = |text: String, color: &str, highlight: bool| {
+        if !options.color {
+            return text;
+        }
+        format!("{}{}{}{}", if highlight { VIOLATION } else { "" }, color, text, RESET)
+    };
+
+    let mut out = String::new();
+    for (i, row) in board.0.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            let highlight = highlights.contains(&(i, j));
+            let mut right = false;
+            let text = match cell {
+                Cell::Wall(WallEnum::Wall) => {
+                    let block = if options.unicode { "█" } else { "#" };
+                    paint(block.repeat(cell_width), SHADED, highlight)
+                }
+                Cell::Wall(WallEnum::Hint(dir, num)) => {
+                    // 向きも数字もないヒントは空白にする
+                    let number = match (dir, *num) {
+                        (DirectionEnum::None, num) if num < 0 => String::new(),
+                        (_, num) if num < 0 => "?".to_string(),
+                        (_, num) => num.to_string(),
+                    };
+                    let pad = " ".repeat((cell_width - 1).saturating_sub(number.chars().count()));
+                    let head = paint(arrow(dir, options.unicode).to_string(), ARROW, highlight);
+                    format!("{}{}", head, paint(format!("{}{}", number, pad), NUMBER, highlight))
+                }
+                Cell::Space(None, None) => paint(format!("{}{}", if options.unicode { '·' } else { '.' }, " ".repeat(cell_width - 1)), DOT, highlight),
+                Cell::Space(one, another) => {
+                    let (c, to_right) = segment(one, another, options.unicode);
+                    right = to_right;
+                    let fill = if !right { ' ' } else if options.unicode { '─' } else { '-' };
+                    paint(format!("{}{}", c, fill.to_string().repeat(cell_width - 1)), LOOP, highlight)
+                }
+                Cell::Unknown => paint(" ".repeat(cell_width), "", highlight),
+            };
+            out.push_str(&text);
+            if mark {
+                out.push(if highlight { '!' } else if !right { ' ' } else if options.unicode { '─' } else { '-' });
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::sat_solutions;
+    use crate::create_board;
+
+    fn solved() -> Board {
+        sat_solutions(&create_board("5/5/g22q"), 1).pop().unwrap()
+    }
+
+    fn plain(unicode: bool, highlights: Vec<(usize, usize)>) -> TerminalOptions {
+        TerminalOptions { unicode, color: false, highlights, check: true }
+    }
+
+    #[test]
+    fn unicode_snapshot() {
+        let expected = concat!(
+            "┌───────┐ \n",
+            "└─┐ ↓2┌─┘ \n",
+            "┌─┘ ██└─┐ \n",
+            "│ ┌───┐ │ \n",
+            "└─┘ ██└─┘ \n",
+        );
+        assert_eq!(to_terminal(&solved(), &plain(true, vec![])), expected);
+    }
+
+    #[test]
+    fn ascii_snapshot() {
+        let expected = concat!(
+            "+-------+ \n",
+            "+-+ v2+-+ \n",
+            "+-+ ##+-+ \n",
+            "| +---+ | \n",
+            "+-+ ##+-+ \n",
+        );
+        assert_eq!(to_terminal(&solved(), &plain(false, vec![])), expected);
+    }
+
+    // 色を使えないときは、目立たせるマスの右に'!'を書き、ほかのマスの右は空けるか線で繋ぐ
+    #[test]
+    fn marker_without_color() {
+        let expected = concat!(
+            "+-----------+  \n",
+            "+--+  v2 +--+  \n",
+            "+--+  ##!+--+  \n",
+            "|  +-----+  |  \n",
+            "+--+  ## +--+  \n",
+        );
+        assert_eq!(to_terminal(&solved(), &plain(false, vec![(2, 2)])), expected);
+
+        // 隣り合う黒マスはcheckが指摘する
+        let mut board = solved();
+        board.0[3][2] = Cell::Wall(WallEnum::Wall);
+        assert!(to_terminal(&board, &plain(false, vec![])).contains('!'));
+        assert!(!to_terminal(&board, &TerminalOptions { check: false, ..plain(false, vec![]) }).contains('!'));
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::Write;
+use crate::Board;
+use crate::render::{layout_highlighted, RenderOptions, Rgb, Scene, ShapeEnum};
+
+fn color(color: Rgb) -> String {
+    format!("{{rgb,255:red,{};green,{};blue,{}}}", color.0, color.1, color.2)
+}
+
+fn path(points: &[(f64, f64)]) -> String {
+    points.iter().map(|(x, y)| format!("({:.2},{:.2})", x, y)).collect::<Vec<_>>().join(" -- ")
+}
+
+// 図形の列をtikzpictureにする。座標はsvgと同じくptで、y軸を下向きにする
+pub fn scene_to_tikz(scene: &Scene) -> String {
+    let mut out = String::new();
+    writeln!(out, "\\begin{{tikzpicture}}[x=1pt,y=-1pt]").unwrap();
+    writeln!(out, "\\fill[color={}] (0,0) rectangle ({:.2},{:.2});", color(scene.background), scene.width, scene.height).unwrap();
+    for shape in scene.shapes.iter() {
+        match shape {
+            ShapeEnum::Rect { x, y, w, h, color: c, opacity } => {
+                writeln!(out, "\\fill[color={}, opacity={}] ({:.2},{:.2}) rectangle ({:.2},{:.2});", color(*c), opacity, x, y, x + w, y + h).unwrap();
+            }
+            ShapeEnum::Line { x1, y1, x2, y2, width, color: c } => {
+                writeln!(out, "\\draw[color={}, line width={:.2}pt, line cap=rect] ({:.2},{:.2}) -- ({:.2},{:.2});", color(*c), width, x1, y1, x2, y2).unwrap();
+            }
+            ShapeEnum::Polyline { points, closed, width, color: c, opacity } => {
+                writeln!(out, "\\draw[color={}, opacity={}, line width={:.2}pt, line cap=round, line join=round] {}{};", color(*c), opacity, width, path(points), if *closed { " -- cycle" } else { "" }).unwrap();
+            }
+            ShapeEnum::Polygon { points, color: c } => {
+                writeln!(out, "\\fill[color={}] {} -- cycle;", color(*c), path(points)).unwrap();
+            }
+            ShapeEnum::Circle { x, y, r, color: c, opacity } => {
+                writeln!(out, "\\fill[color={}, opacity={}] ({:.2},{:.2}) circle[radius={:.2}pt];", color(*c), opacity, x, y, r).unwrap();
+            }
+            ShapeEnum::Text { x, y, size, text, color: c } => {
+                writeln!(out, "\\node[color={}, inner sep=0pt, font=\\sffamily\\fontsize{{{:.1}}}{{{:.1}}}\\selectfont] at ({:.2},{:.2}) {{{}}};", color(*c), size, size, x, y, text).unwrap();
+            }
+        }
+    }
+    writeln!(out, "\\end{{tikzpicture}}").unwrap();
+    out
+}
+
+// 文書に埋め込むtikzpicture。highlightsのマスに色を付ける
+pub fn to_tikz(board: &Board, options: &RenderOptions, highlights: &[(usize, usize)]) -> String {
+    scene_to_tikz(&layout_highlighted(board, options, highlights))
+}
+
+// それだけでコンパイルできるstandaloneの文書
+pub fn to_tikz_document(board: &Board, options: &RenderOptions, highlights: &[(usize, usize)]) -> String {
+    format!("\\documentclass[tikz]{{standalone}}\n\\begin{{document}}\n{}\\end{{document}}\n", to_tikz(board, options, highlights))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_board;
+    use crate::render::ThemeEnum;
+
+    #[test]
+    fn document_with_highlights() {
+        let options = RenderOptions::default();
+        let document = to_tikz_document(&create_board("5/5/g22q"), &options, &[(1, 2), (4, 0), (9, 9)]);
+        assert!(document.starts_with("\\documentclass[tikz]{standalone}\n\\begin{document}\n\\begin{tikzpicture}"));
+        assert!(document.ends_with("\\end{tikzpicture}\n\\end{document}\n"));
+        assert_eq!(document.matches("\\begin{tikzpicture}").count(), 1);
+        assert_eq!(document.matches("\\end{tikzpicture}").count(), 1);
+
+        // 盤面の外のマスは描かない
+        let fill = format!("\\fill[color={}, opacity=0.6]", color(ThemeEnum::Light.palette().highlight));
+        assert_eq!(document.matches(&fill).count(), 2);
+        assert!(document.contains(&format!("{} (90.00,54.00) rectangle (126.00,90.00);", fill)));
+        assert!(document.contains(&format!("{} (18.00,162.00) rectangle (54.00,198.00);", fill)));
+    }
+}
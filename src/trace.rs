@@ -0,0 +1,305 @@
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use crate::{create_priority_queue, solve_ordered, Board, CheckResultEnum, SearchOrder};
+use crate::terminal::{to_terminal, TerminalOptions};
+
+// 探索の記録。盤面はその手を打った後のもの
+#[derive(Clone)]
+pub enum TraceEventEnum {
+    Guess { cell: (usize, usize), board: Board },             // 候補が複数あるマスを仮に決めた
+    Forced { cell: (usize, usize), board: Board },            // 候補が一つしかないマスを決めた
+    Propagate { cells: Vec<(usize, usize)>, board: Board },   // 決めたマスに伴って決まったマス
+    Backtrack { cell: (usize, usize), board: Board },         // 仮定が矛盾したので戻った
+}
+
+impl TraceEventEnum {
+    pub fn board(&self) -> &Board {
+        match self {
+            TraceEventEnum::Guess { board, .. }
+            | TraceEventEnum::Forced { board, .. }
+            | TraceEventEnum::Propagate { board, .. }
+            | TraceEventEnum::Backtrack { board, .. } => board,
+        }
+    }
+
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        match self {
+            TraceEventEnum::Guess { cell, .. } | TraceEventEnum::Forced { cell, .. } | TraceEventEnum::Backtrack { cell, .. } => vec![*cell],
+            TraceEventEnum::Propagate { cells, .. } => cells.clone(),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            TraceEventEnum::Guess { .. } => "guess",
+            TraceEventEnum::Forced { .. } => "forced",
+            TraceEventEnum::Propagate { .. } => "propagate",
+            TraceEventEnum::Backtrack { .. } => "backtrack",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            TraceEventEnum::Guess { .. } => "\x1b[33m",
+            TraceEventEnum::Forced { .. } => "\x1b[32m",
+            TraceEventEnum::Propagate { .. } => "\x1b[36m",
+            TraceEventEnum::Backtrack { .. } => "\x1b[31m",
+        }
+    }
+}
+
+// 探索しながら、仮定、伝播、後戻りを記録する
+pub fn solve_traced(board: &Board) -> (CheckResultEnum, Option<Board>, Vec<TraceEventEnum>) {
+    let mut order = SearchOrder::traced();
+    let (result, answer) = solve_ordered(board, &create_priority_queue(board), &mut order);
+    (result, answer, order.take_trace())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandEnum {
+    Pause, // 止める、再開する
+    Step,  // 止めて一手進める
+    Faster,
+    Slower,
+    Quit,
+}
+
+// 端末では一行ずつしか読めないので、Enterで確定した文字をコマンドにする
+pub fn parse_command(line: &str) -> Option<CommandEnum> {
+    match line.trim() {
+        "" | "p" => Some(CommandEnum::Pause),
+        "s" | "n" => Some(CommandEnum::Step),
+        "+" | "f" => Some(CommandEnum::Faster),
+        "-" | "w" => Some(CommandEnum::Slower),
+        "q" => Some(CommandEnum::Quit),
+        _ => None,
+    }
+}
+
+pub struct Player {
+    events: Vec<TraceEventEnum>,
+    pub position: usize,
+    pub paused: bool,
+    pub delay: Duration,
+    pub options: TerminalOptions,
+}
+
+impl Player {
+    pub fn new(events: Vec<TraceEventEnum>, delay: Duration) -> Player {
+        Player { events, position: 0, paused: false, delay, options: TerminalOptions::default() }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.position + 1 >= self.events.len()
+    }
+
+    // Quitならfalse
+    pub fn handle(&mut self, command: CommandEnum) -> bool {
+        match command {
+            CommandEnum::Pause => self.paused = !self.paused,
+            CommandEnum::Step => {
+                self.paused = true;
+                self.advance();
+            }
+            CommandEnum::Faster => self.delay = (self.delay / 2).max(Duration::from_millis(1)),
+            CommandEnum::Slower => self.delay = (self.delay * 2).min(Duration::from_secs(10)),
+            CommandEnum::Quit => return false,
+        }
+        true
+    }
+
+    pub fn advance(&mut self) {
+        if !self.finished() {
+            self.position += 1;
+        }
+    }
+
+    // 今の手の盤面。動かしたマスを目立たせる
+    pub fn frame(&self) -> String {
+        let event = match self.events.get(self.position) {
+            Some(event) => event,
+            None => return "no steps\n".to_string(),
+        };
+        let guesses = self.events[..=self.position].iter().filter(|e| matches!(e, TraceEventEnum::Guess { .. })).count();
+        let label = if self.options.color { format!("{}{}\x1b[0m", event.color(), event.label()) } else { event.label().to_string() };
+        let cells: Vec<String> = event.cells().iter().map(|(i, j)| format!("({},{})", i, j)).collect();
+        let options = TerminalOptions { highlights: event.cells(), ..self.options.clone() };
+        format!(
+            "step {}/{}  {} {}  guesses {}  {}ms{}\n{}",
+            self.position + 1,
+            self.events.len(),
+            label,
+            cells.join(" "),
+            guesses,
+            self.delay.as_millis(),
+            if self.paused { "  [paused]" } else { "" },
+            to_terminal(event.board(), &options),
+        )
+    }
+
+    // 止めていなければdelayごとに一手進める。最後の手まで来たら終わる
+    pub fn play(&mut self, input: &Receiver<CommandEnum>, out: &mut dyn Write) -> io::Result<()> {
+        loop {
+            write!(out, "\x1b[2J\x1b[H{}", self.frame())?;
+            out.flush()?;
+            if self.finished() && !self.paused {
+                return Ok(());
+            }
+            let command = if self.paused {
+                input.recv().ok()
+            } else {
+                match input.recv_timeout(self.delay) {
+                    Ok(command) => Some(command),
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.advance();
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => None,
+                }
+            };
+            match command {
+                Some(command) => {
+                    if !self.handle(command) {
+                        return Ok(());
+                    }
+                }
+                // 入力が閉じたら、止めずに最後まで流す
+                None => {
+                    while !self.finished() {
+                        thread::sleep(self.delay);
+                        self.advance();
+                        write!(out, "\x1b[2J\x1b[H{}", self.frame())?;
+                        out.flush()?;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+// 標準入力からコマンドを受けながら、端末で再生する
+pub fn replay(events: Vec<TraceEventEnum>, delay: Duration) -> io::Result<()> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let command = match line.ok().as_deref().and_then(parse_command) {
+                Some(command) => command,
+                None => continue,
+            };
+            if sender.send(command).is_err() || command == CommandEnum::Quit {
+                break;
+            }
+        }
+    });
+    let mut player = Player::new(events, delay);
+    player.play(&receiver, &mut io::stdout())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_board, playable, Cell, DirectionEnum, WallEnum};
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse_command(""), Some(CommandEnum::Pause));
+        assert_eq!(parse_command(" p \n"), Some(CommandEnum::Pause));
+        assert_eq!(parse_command("s"), Some(CommandEnum::Step));
+        assert_eq!(parse_command("n"), Some(CommandEnum::Step));
+        assert_eq!(parse_command("+"), Some(CommandEnum::Faster));
+        assert_eq!(parse_command("w"), Some(CommandEnum::Slower));
+        assert_eq!(parse_command("q"), Some(CommandEnum::Quit));
+        assert_eq!(parse_command("x"), None);
+        assert_eq!(parse_command("quit"), None);
+    }
+
+    // 探索がそのマスで試す候補のうち、置けるもの
+    fn playable_at(board: &Board, (i, j): (usize, usize)) -> usize {
+        let dirs = [DirectionEnum::Up, DirectionEnum::Down, DirectionEnum::Left, DirectionEnum::Right];
+        let candidate: Vec<Cell> = match &board.0[i][j] {
+            Cell::Unknown => std::iter::once(Cell::Wall(WallEnum::Wall)).chain(dirs.iter().map(|d| Cell::Space(Some(d.clone()), None))).collect(),
+            Cell::Space(None, _) => dirs.iter().map(|d| Cell::Space(Some(d.clone()), None)).collect(),
+            Cell::Space(Some(one), None) => dirs.iter().map(|d| Cell::Space(Some(one.clone()), Some(d.clone()))).collect(),
+            _ => panic!("({}, {}) is already decided", i, j),
+        };
+        playable(board, i, j, candidate).len()
+    }
+
+    // 手を打つ前の盤面は、一つ前の記録の盤面
+    #[test]
+    fn forced_only_with_one_playable_candidate() {
+        let board = create_board("5/5/g22q");
+        let (result, _, events) = solve_traced(&board);
+        assert!(matches!(result, CheckResultEnum::Complete));
+        let mut before = &board;
+        let (mut forced, mut guesses) = (0, 0);
+        for event in events.iter() {
+            match event {
+                TraceEventEnum::Forced { cell, .. } => {
+                    assert_eq!(playable_at(before, *cell), 1);
+                    forced += 1;
+                }
+                TraceEventEnum::Guess { cell, .. } => {
+                    assert!(playable_at(before, *cell) > 1);
+                    guesses += 1;
+                }
+                _ => {}
+            }
+            before = event.board();
+        }
+        assert!(forced > 0 && guesses > 0);
+    }
+
+    fn player() -> Player {
+        let (_, _, events) = solve_traced(&create_board("5/5/g22q"));
+        let mut player = Player::new(events, Duration::from_millis(100));
+        player.options.color = false;
+        player
+    }
+
+    #[test]
+    fn handle_and_advance() {
+        let mut player = player();
+        assert!(player.handle(CommandEnum::Pause));
+        assert!(player.paused);
+        assert!(player.handle(CommandEnum::Pause));
+        assert!(!player.paused);
+        assert!(player.handle(CommandEnum::Step));
+        assert!(player.paused);
+        assert_eq!(player.position, 1);
+        assert!(player.handle(CommandEnum::Faster));
+        assert_eq!(player.delay, Duration::from_millis(50));
+        assert!(player.handle(CommandEnum::Slower));
+        assert!(player.handle(CommandEnum::Slower));
+        assert_eq!(player.delay, Duration::from_millis(200));
+        assert!(!player.handle(CommandEnum::Quit));
+        // 最後の手より先には進まない
+        while !player.finished() {
+            player.advance();
+        }
+        let last = player.position;
+        player.advance();
+        assert_eq!(player.position, last);
+    }
+
+    #[test]
+    fn play_until_quit() {
+        let mut player = player();
+        let (sender, receiver) = channel();
+        for command in [CommandEnum::Pause, CommandEnum::Step, CommandEnum::Quit] {
+            sender.send(command).unwrap();
+        }
+        let mut out: Vec<u8> = Vec::new();
+        player.play(&receiver, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let frames: Vec<&str> = out.split("\x1b[2J\x1b[H").filter(|frame| !frame.is_empty()).collect();
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].starts_with("step 1/") && !frames[0].contains("[paused]"));
+        assert!(frames[1].starts_with("step 1/") && frames[1].contains("[paused]"));
+        assert!(frames[2].starts_with("step 2/") && frames[2].contains("[paused]"));
+        assert_eq!(player.position, 1);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{clear_board, Board, Cell, CheckResultInvalidEnum, DirectionEnum, WallEnum};
+use crate::cnf::SatSession;
+
+// 解がなくなる原因になっているヒントの集合
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsatCore {
+    pub clues: Vec<(usize, usize)>,
+    pub checks: usize, // SATソルバで確かめた回数
+}
+
+impl UnsatCore {
+    // checkと同じ形で、エディタで強調するマス
+    pub fn highlights(&self) -> Vec<CheckResultInvalidEnum> {
+        self.clues.iter().map(|(i, j)| CheckResultInvalidEnum::Hint(*i as i32, *j as i32)).collect()
+    }
+}
+
+fn satisfiable(board: &Board) -> bool {
+    match SatSession::new(board) {
+        Ok(mut session) => session.solve(&[]).is_some(),
+        Err(_) => false,
+    }
+}
+
+// ヒントの数字を外す。マス自体は残るので線は通れない
+fn relax(board: &mut Board, i: usize, j: usize) {
+    board.0[i][j] = Cell::Wall(WallEnum::Hint(DirectionEnum::None, -2));
+}
+
+// ヒントを一つずつ外して、解がないままなら外したままにする
+// 数字をすべて外しても解がないときは、ヒントのマスの置き場所が原因なので、ヒントのマスをすべて返す
+pub fn unsat_core(board: &Board) -> Option<UnsatCore> {
+    let mut current = clear_board(board);
+    let mut checks = 1;
+    if satisfiable(&current) {
+        return None;
+    }
+    let mut clues = Vec::new();
+    for (i, row) in current.0.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            if let Cell::Wall(WallEnum::Hint(dir, num)) = cell {
+                if *dir != DirectionEnum::None && *num >= 0 {
+                    clues.push((i, j));
+                }
+            }
+        }
+    }
+
+    let mut core = Vec::new();
+    for (i, j) in clues {
+        let mut next = current.clone();
+        relax(&mut next, i, j);
+        checks += 1;
+        if satisfiable(&next) {
+            core.push((i, j));
+        } else {
+            current = next;
+        }
+    }
+    if core.is_empty() {
+        for (i, row) in current.0.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                if let Cell::Wall(WallEnum::Hint(..)) = cell {
+                    core.push((i, j));
+                }
+            }
+        }
+    }
+    Some(UnsatCore { clues: core, checks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_board;
+
+    #[test]
+    fn satisfiable_board_has_no_core() {
+        assert_eq!(unsat_core(&create_board("5/5/g22q")), None);
+    }
+
+    #[test]
+    fn bumped_clue_is_the_core() {
+        // 下向きの2を3にすると解がなくなる
+        let core = unsat_core(&create_board("5/5/g23q")).unwrap();
+        assert_eq!(core.clues, vec![(1, 2)]);
+        assert_eq!(core.highlights(), vec![CheckResultInvalidEnum::Hint(1, 2)]);
+    }
+
+    #[test]
+    fn clue_cells_alone_block_the_loop() {
+        // 2x2の一マスがヒントなら、残りの3マスではループを作れない
+        let mut board = create_board("2/2/");
+        board.0[0][0] = Cell::Wall(WallEnum::Hint(DirectionEnum::Right, 0));
+        let core = unsat_core(&board).unwrap();
+        assert_eq!(core.clues, vec![(0, 0)]);
+    }
+}